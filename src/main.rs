@@ -1,29 +1,116 @@
-use std::{env::args, fs};
+use std::{env::args, fs, process};
 
-use voltage_lexer::Lexer;
+use voltage_lexer::{dump, Lexer};
 use voltage_parser::Parser;
 
-fn read_to_char_vec() -> Vec<char> {
-    fs::read_to_string(args().nth(1).unwrap())
-        .unwrap()
-        .chars()
-        .collect()
-}
+const USAGE: &str = "usage: voltage <file>
+       voltage tokens [--json] <file>";
 
 fn main() {
-    let source = read_to_char_vec();
-    let mut lexer = Lexer::new(source);
-    let tokens = lexer.lex();
-    // println!("{:#?}", tokens);
+    let args: Vec<String> = args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("tokens") => tokens(&args[1..]),
+        Some(path) => run(path),
+        None => {
+            eprintln!("{USAGE}");
+            process::exit(2);
+        }
+    }
+}
+
+/// `voltage tokens [--json] <file>`: prints every token, trivia included,
+/// with its span and kind.
+fn tokens(args: &[String]) {
+    let json = args.iter().any(|arg| arg == "--json");
+    let Some(path) = args.iter().find(|arg| !arg.starts_with("--")) else {
+        eprintln!("{USAGE}");
+        process::exit(2);
+    };
+
+    let source = read_source(path);
+    let mut tokens = vec![];
+    let mut errors = vec![];
+    for token in Lexer::with_trivia(&source) {
+        match token {
+            Ok(token) => tokens.push(token),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    if json {
+        println!("{}", dump::to_json(&source, &tokens));
+    } else {
+        print!("{}", dump::to_text(&source, &tokens));
+    }
+
+    if !errors.is_empty() {
+        for error in errors {
+            eprintln!("{error}");
+        }
+        process::exit(1);
+    }
+}
+
+/// Reads the file at `path`, exiting with the error if it can not be read.
+fn read_source(path: &str) -> String {
+    match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("{path}: {error}");
+            process::exit(1);
+        }
+    }
+}
+
+fn run(path: &str) {
+    let source = read_source(path);
+    let mut lexer = Lexer::new(&source);
+    let tokens = match lexer.lex() {
+        Ok(tokens) => tokens,
+        Err(errors) => {
+            for error in errors {
+                eprintln!("{error}");
+            }
+            process::exit(1);
+        }
+    };
     let mut parser = Parser::new(tokens);
-    let ast = parser.parse();
-    println!("{:#?}", ast);
+    let (mut ast, errors) = parser.parse();
+    if !errors.is_empty() {
+        for error in errors {
+            eprintln!("{error}");
+        }
+        process::exit(1);
+    }
+    let errors = voltage_typechecker::check(&mut ast);
+    if !errors.is_empty() {
+        for error in errors {
+            eprintln!("{error}");
+        }
+        process::exit(1);
+    }
+    let ast = match voltage_parser::resolve(ast) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            for error in errors {
+                eprintln!("{error}");
+            }
+            process::exit(1);
+        }
+    };
+    println!("{:#?}", &ast[..]);
     cfg_if::cfg_if! {
         if #[cfg(feature = "builtin")] {
             use voltage_codegen::builtin::Engine;
 
             let mut engine = Engine::new();
-            let contents = engine.exectute(ast);
+            let contents = match engine.exectute(ast) {
+                Ok(contents) => contents,
+                Err(error) => {
+                    eprintln!("{error}");
+                    process::exit(1);
+                }
+            };
             #[cfg(feature = "json_abi")]
             fs::write("./a.out", contents).unwrap()
         }
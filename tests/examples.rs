@@ -0,0 +1,222 @@
+//! Engine tests. The examples are checked against `tests/golden/*.values`,
+//! blessed the same way as the lexer's token dumps.
+
+use std::{env, fs, path::Path, rc::Rc};
+
+use voltage_codegen::builtin::{Engine, MAX_CALL_DEPTH};
+use voltage_lexer::Lexer;
+use voltage_parser::{Parser, Resolved};
+
+/// Checks and resolves `source`, which must be free of errors.
+fn compile(source: &str) -> Resolved {
+    let tokens = Lexer::new(source).lex().unwrap();
+    let (mut ast, errors) = Parser::new(tokens).parse();
+    assert!(errors.is_empty(), "{errors:?}");
+    let errors = voltage_typechecker::check(&mut ast);
+    assert!(errors.is_empty(), "{errors:?}");
+    voltage_parser::resolve(ast).unwrap()
+}
+
+/// Runs `source`, returning each top-level variable and function as
+/// `name = value`, sorted by name, or the runtime error it stopped at.
+fn run(source: &str) -> String {
+    let mut engine = Engine::new();
+    run_on(&mut engine, source)
+}
+
+fn run_on(engine: &mut Engine, source: &str) -> String {
+    if let Err(error) = engine.exectute(compile(source)) {
+        return error.to_string();
+    }
+    let mut variables = engine.env.borrow().variables.clone();
+    variables.sort_by(|a, b| a.name.cmp(&b.name));
+    variables
+        .into_iter()
+        .map(|variable| format!("{} = {}\n", variable.name, variable.value))
+        .collect()
+}
+
+#[test]
+fn examples_end_with_golden_values() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let bless = env::var_os("VOLTAGE_BLESS").is_some();
+
+    for entry in fs::read_dir(root.join("examples")).unwrap() {
+        let example = entry.unwrap().path();
+        if example.extension().is_none_or(|ext| ext != "volt") {
+            continue;
+        }
+        let actual = run(&fs::read_to_string(&example).unwrap());
+        let golden = root
+            .join("tests/golden")
+            .join(example.file_stem().unwrap())
+            .with_extension("values");
+        if bless {
+            fs::write(&golden, &actual).unwrap();
+        } else {
+            let expected = fs::read_to_string(&golden).unwrap_or_default();
+            assert_eq!(actual, expected, "values of {} changed", example.display());
+        }
+    }
+}
+
+#[test]
+fn return_leaves_nested_blocks() {
+    let source = "func sign(x: int): int
+    let mut i: int = 0
+    while i < 10 {
+        if x < 0 {
+            return -1
+        }
+        for _ in 0..x {
+            return 1
+        }
+        i += 1
+    }
+    return 0
+end
+
+func count_down(n: int): int
+    if n == 0 {
+        return 0
+    }
+    let below = count_down(n - 1)
+    return n + below
+end
+
+let mut total: int = 0
+
+func skip_negative(n: int)
+    if n < 0 {
+        return
+    }
+    total = total + n
+end
+
+skip_negative(-3)
+skip_negative(3)
+
+let a = sign(-5)
+let b = sign(5)
+let c = sign(0)
+let sum = count_down(4)
+";
+    assert_eq!(
+        run(source),
+        "a = -1\nb = 1\nc = 0\ncount_down = func count_down\nsign = func sign\n\
+         skip_negative = func skip_negative\nsum = 10\ntotal = 3\n"
+    );
+}
+
+#[test]
+fn functions_can_call_ones_declared_after_them() {
+    let source = "let four = is_even(4)
+
+func is_even(n: int): bool
+    if n == 0 {
+        return true
+    }
+    return is_odd(n - 1)
+end
+
+func is_odd(n: int): bool
+    if n == 0 {
+        return false
+    }
+    return is_even(n - 1)
+end
+
+let seven = is_odd(7)
+";
+    assert_eq!(
+        run(source),
+        "four = true\nis_even = func is_even\nis_odd = func is_odd\nseven = true\n"
+    );
+}
+
+#[test]
+fn runtime_errors_carry_a_stack_trace() {
+    let source = "func double(x: i8): i8
+    return x * 2
+end
+
+func apply(f: func(i8): i8, x: i8): i8
+    return f(x) + 1
+end
+
+let ok = apply(double, 10)
+let overflows = apply(double, 100)
+";
+    assert_eq!(
+        run(source),
+        "[RUNTIME] Error @ 2:12-2:17: 100 * 2 overflows i8
+    in double called @ 6:12-6:16
+    in apply called @ 10:17-10:35"
+    );
+
+    let source = "let zero: int = 0\nlet x: int = 7 / zero\n";
+    assert_eq!(
+        run(source),
+        "[RUNTIME] Error @ 2:14-2:22: division of 7 by zero"
+    );
+}
+
+#[test]
+fn unbounded_recursion_is_a_stack_overflow() {
+    let source = "func forever(n: int): int
+    return forever(n + 1)
+end
+
+let x = forever(0)
+";
+    assert_eq!(
+        run(source),
+        format!(
+            "[RUNTIME] Error @ 2:12-2:26: stack overflow: calls nested more than 1024 deep
+    in forever called @ 2:12-2:26 (×{})
+    in forever called @ 5:9-5:19",
+            MAX_CALL_DEPTH - 1
+        )
+    );
+}
+
+#[test]
+fn an_engine_runs_each_program_in_a_scope_of_its_own() {
+    let mut engine = Engine::new();
+    assert_eq!(
+        run_on(&mut engine, "let a = 1\nlet mut b = 2\n"),
+        "a = 1\nb = 2\n"
+    );
+    assert_eq!(
+        run_on(&mut engine, "let c = 3\nlet mut d = c + 1\nd = d * 2\n"),
+        "c = 3\nd = 8\n"
+    );
+}
+
+#[test]
+fn scopes_are_freed_once_their_functions_are_done() {
+    let source = "func outer(n: int): int
+    func inner(): int
+        return n
+    end
+    let twice = func(x: int): int
+        return x * 2
+    end
+    let again = twice
+    return again(inner())
+end
+
+let add_one = func(x: int): int
+    return x + 1
+end
+let x = add_one(outer(4))
+";
+    let mut engine = Engine::new();
+    assert_eq!(
+        run_on(&mut engine, source),
+        "add_one = func <anonymous>\nouter = func outer\nx = 9\n"
+    );
+    // Neither the functions declared at the top level nor the scope of the
+    // call to `outer`, had it outlived the call, hold on to the top level
+    assert_eq!(Rc::strong_count(&engine.env), 1);
+}
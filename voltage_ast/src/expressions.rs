@@ -1,22 +1,97 @@
-use crate::Operator;
+use voltage_lexer::span::Span;
+
+use crate::{
+    statements::{write_function, Statement},
+    FuncParam, Operator, Slot, Type,
+};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, PartialOrd)]
 pub enum Expression {
-    StringLiteral { val: String },
-    Identifier { val: String },
-    IntLiteral { val: i64 },
-    BooleanLiteral { val: bool },
-    FloatLiteral { val: f64 },
-    CharLiteral { val: char },
+    StringLiteral { val: String, span: Span },
+    /// A variable, with its slot once resolved.
+    Identifier { val: String, slot: Option<Slot>, span: Span },
+    IntLiteral { val: i64, r#type: Type, span: Span },
+    BooleanLiteral { val: bool, span: Span },
+    FloatLiteral { val: f64, span: Span },
+    CharLiteral { val: char, span: Span },
     
-    FunctionCall { name: Box<Expression>, params: Vec<Expression> },
+    FunctionCall { name: Box<Expression>, params: Vec<Expression>, span: Span },
     BinaryExpr {
         op: Operator,
         lhs: Box<Expression>,
-        rhs: Box<Expression>
+        rhs: Box<Expression>,
+        span: Span,
     },
     UnaryExpr {
         op: Operator,
         child: Box<Expression>,
+        span: Span,
+    },
+    /// `expr as r#type`
+    Cast {
+        expr: Box<Expression>,
+        r#type: Type,
+        span: Span,
+    },
+    /// An anonymous function, `func(x: int): int ... end`
+    Function {
+        params: Vec<FuncParam>,
+        return_type: Type,
+        body: Vec<Statement>,
+        span: Span,
     },
-}
\ No newline at end of file
+}
+
+impl Expression {
+    /// The region of source the expression was parsed from.
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::StringLiteral { span, .. }
+            | Expression::Identifier { span, .. }
+            | Expression::IntLiteral { span, .. }
+            | Expression::BooleanLiteral { span, .. }
+            | Expression::FloatLiteral { span, .. }
+            | Expression::CharLiteral { span, .. }
+            | Expression::FunctionCall { span, .. }
+            | Expression::BinaryExpr { span, .. }
+            | Expression::UnaryExpr { span, .. }
+            | Expression::Cast { span, .. }
+            | Expression::Function { span, .. } => *span,
+        }
+    }
+}
+
+/// Prints the expression as an S-expression, e.g. `(* (+ a b) c)`, which
+/// makes the grouping chosen by the parser explicit.
+impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expression::StringLiteral { val, .. } => write!(f, "{val:?}"),
+            Expression::Identifier { val, .. } => write!(f, "{val}"),
+            Expression::IntLiteral { val, .. } => write!(f, "{val}"),
+            Expression::BooleanLiteral { val, .. } => write!(f, "{val}"),
+            Expression::FloatLiteral { val, .. } => write!(f, "{val:?}"),
+            Expression::CharLiteral { val, .. } => write!(f, "{val:?}"),
+            Expression::FunctionCall { name, params, .. } => {
+                write!(f, "(call {name}")?;
+                for param in params {
+                    write!(f, " {param}")?;
+                }
+                write!(f, ")")
+            }
+            Expression::BinaryExpr { op, lhs, rhs, .. } => write!(f, "({op} {lhs} {rhs})"),
+            Expression::UnaryExpr { op, child, .. } => write!(f, "({op} {child})"),
+            Expression::Cast { expr, r#type, .. } => write!(f, "(as {expr} {type})"),
+            Expression::Function {
+                params,
+                return_type,
+                body,
+                ..
+            } => {
+                write!(f, "(func ")?;
+                write_function(f, params, return_type, body)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use voltage_lexer::span::Span;
+
 pub mod expressions;
 pub mod statements;
 
@@ -7,22 +9,74 @@ pub struct FuncParam {
     pub r#type: Type,
 }
 
+/// Where a variable lives, resolved before running: the scope `depth`
+/// blocks out from the one it is used in, and its position among the
+/// variables declared there.
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize, PartialEq, PartialOrd)]
+pub struct Slot {
+    pub depth: usize,
+    pub index: usize,
+}
+
+/// Whether a variable can be assigned to after its declaration.
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize, PartialEq, PartialOrd)]
+pub enum Mutability {
+    /// Declared with `let mut`
+    Mutable,
+    /// Declared with `let`
+    Immutable,
+    /// Declared with `const`
+    Const,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, PartialOrd)]
 pub enum Operator {
     Plus,
     Minus,
     Multiplication,
     Division,
-}
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, PartialOrd)]
-pub enum CmpOperators {
+    // Arithmetic that wraps around on overflow, written `+%`, `-%` and `*%`
+    WrappingPlus,
+    WrappingMinus,
+    WrappingMultiplication,
+
+    // Comparisons, which evaluate to a `Bool`
     Equal,
     NotEqual,
     GreaterThen,
     LessThen,
     GreaterThenOrEqual,
     LessThenOrEqual,
+
+    // Logical operators, which take and evaluate to a `Bool`
+    And,
+    Or,
+    Not,
+}
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Operator::Plus => "+",
+            Operator::Minus => "-",
+            Operator::Multiplication => "*",
+            Operator::Division => "/",
+            Operator::WrappingPlus => "+%",
+            Operator::WrappingMinus => "-%",
+            Operator::WrappingMultiplication => "*%",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::GreaterThen => ">",
+            Operator::LessThen => "<",
+            Operator::GreaterThenOrEqual => ">=",
+            Operator::LessThenOrEqual => "<=",
+            Operator::And => "and",
+            Operator::Or => "or",
+            Operator::Not => "not",
+        };
+        write!(f, "{symbol}")
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, PartialOrd)]
@@ -34,9 +88,43 @@ pub enum Type {
     Int64,
     Int,
     Float,
+    Bool,
+    String,
     Void,
     Unknown,
+    /// A name that is not a type, left for the type checker to report
+    Undefined(Box<TypeName>),
     Nil,
+    /// `func(int, int): int`
+    Function {
+        params: Vec<Type>,
+        return_type: Box<Type>,
+    },
+}
+
+/// A type name as written at `span`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, PartialOrd)]
+pub struct TypeName {
+    pub name: String,
+    pub span: Span,
+}
+
+impl Type {
+    pub fn is_int(&self) -> bool {
+        self.int_range().is_some()
+    }
+
+    /// The values an integer of this type can hold, or `None` if it is not
+    /// an integer type.
+    pub fn int_range(&self) -> Option<std::ops::RangeInclusive<i128>> {
+        match self {
+            Type::Int8 => Some(i8::MIN.into()..=i8::MAX.into()),
+            Type::Int16 => Some(i16::MIN.into()..=i16::MAX.into()),
+            Type::Int32 => Some(i32::MIN.into()..=i32::MAX.into()),
+            Type::Int64 | Type::Int => Some(i64::MIN.into()..=i64::MAX.into()),
+            _ => None,
+        }
+    }
 }
 
 impl From<&str> for Type {
@@ -49,6 +137,8 @@ impl From<&str> for Type {
             "i64" => Self::Int64,
             "int" => Self::Int,
             "float" => Self::Float,
+            "bool" => Self::Bool,
+            "string" => Self::String,
             "nil" | "null" => Self::Nil,
             "void" => Self::Void,
             _ => Self::Unknown,
@@ -56,8 +146,36 @@ impl From<&str> for Type {
     }
 }
 
-impl ToString for Type {
-    fn to_string(&self) -> String {
-        todo!()
+/// The names a type can be written as.
+pub const TYPE_NAMES: [&str; 12] = [
+    "char", "i8", "i16", "i32", "i64", "int", "float", "bool", "string", "nil", "null", "void",
+];
+
+/// Prints the type as it is written in source, e.g. `i8`.
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Type::Char => "char",
+            Type::Int8 => "i8",
+            Type::Int16 => "i16",
+            Type::Int32 => "i32",
+            Type::Int64 => "i64",
+            Type::Int => "int",
+            Type::Float => "float",
+            Type::Bool => "bool",
+            Type::String => "string",
+            Type::Void => "void",
+            Type::Unknown => "unknown",
+            Type::Undefined(undefined) => &undefined.name,
+            Type::Nil => "nil",
+            Type::Function {
+                params,
+                return_type,
+            } => {
+                let params: Vec<String> = params.iter().map(ToString::to_string).collect();
+                return write!(f, "func({}): {return_type}", params.join(", "));
+            }
+        };
+        write!(f, "{name}")
     }
 }
@@ -1,26 +1,161 @@
-use crate::{expressions::Expression, Type, FuncParam, CmpOperators};
+use voltage_lexer::span::Span;
+
+use crate::{expressions::Expression, Type, FuncParam, Mutability, Slot};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, PartialOrd)]
 pub enum Statement {
     VariableDeclaration {
         name: String,
         value: Expression,
-        r#type: Type,
+        /// `None` until inferred by the type checker when the declaration
+        /// has no type annotation.
+        r#type: Option<Type>,
+        mutability: Mutability,
+        span: Span,
+    },
+    /// `name = value`. Compound assignments such as `x += 1` are stored as
+    /// `x = x + 1`.
+    Assignment {
+        name: String,
+        slot: Option<Slot>,
+        value: Expression,
+        span: Span,
     },
     FunctionDeclaration {
         name: String,
         params: Vec<FuncParam>,
         body: Vec<Statement>,
         return_type: Type,
+        span: Span,
     },
     IfStatement {
-        expr1: Expression,
-        cmp_op: CmpOperators,
-        expr2: Expression,
-        body: Vec<Statement>
+        condition: Expression,
+        body: Vec<Statement>,
+        /// The `else` block. An `else if` is stored as a single nested
+        /// `IfStatement`.
+        else_body: Option<Vec<Statement>>,
+        span: Span,
+    },
+    While {
+        condition: Expression,
+        body: Vec<Statement>,
+        span: Span,
+    },
+    /// `for variable in start..end`, counting up from `start` to `end`
+    /// exclusive.
+    For {
+        variable: String,
+        start: Expression,
+        end: Expression,
+        body: Vec<Statement>,
+        span: Span,
     },
+    Break { span: Span },
+    Continue { span: Span },
     Return {
-        value: Expression
+        /// `None` for a bare `return` from a `void` function.
+        value: Option<Expression>,
+        span: Span,
     },
-    ExprStatement { expr: Expression }
+    ExprStatement { expr: Expression, span: Span }
+}
+
+impl Statement {
+    /// The region of source the statement was parsed from.
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::VariableDeclaration { span, .. }
+            | Statement::Assignment { span, .. }
+            | Statement::FunctionDeclaration { span, .. }
+            | Statement::IfStatement { span, .. }
+            | Statement::While { span, .. }
+            | Statement::For { span, .. }
+            | Statement::Break { span }
+            | Statement::Continue { span }
+            | Statement::Return { span, .. }
+            | Statement::ExprStatement { span, .. } => *span,
+        }
+    }
+}
+
+/// Prints a block as a parenthesised list of statements.
+fn write_block(f: &mut std::fmt::Formatter<'_>, body: &[Statement]) -> std::fmt::Result {
+    write!(f, "(")?;
+    for (i, statement) in body.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{statement}")?;
+    }
+    write!(f, ")")
+}
+
+/// Prints the parameters, return type and body of a function, e.g.
+/// `((a int) (b int)) int ((return (+ a b)))`.
+pub(crate) fn write_function(
+    f: &mut std::fmt::Formatter<'_>,
+    params: &[FuncParam],
+    return_type: &Type,
+    body: &[Statement],
+) -> std::fmt::Result {
+    write!(f, "(")?;
+    for (i, param) in params.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "({} {})", param.name, param.r#type)?;
+    }
+    write!(f, ") {return_type} ")?;
+    write_block(f, body)
+}
+
+/// Prints the statement as an S-expression in the style of `Expression`,
+/// e.g. `(let x int (+ 1 2))`. Types are printed by name, or as `_` when
+/// not known yet.
+impl std::fmt::Display for Statement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Statement::VariableDeclaration { name, value, r#type, mutability, .. } => {
+                let keyword = match mutability {
+                    Mutability::Mutable => "let mut",
+                    Mutability::Immutable => "let",
+                    Mutability::Const => "const",
+                };
+                match r#type {
+                    Some(r#type) => write!(f, "({keyword} {name} {type} {value})"),
+                    None => write!(f, "({keyword} {name} _ {value})"),
+                }
+            }
+            Statement::Assignment { name, value, .. } => write!(f, "(= {name} {value})"),
+            Statement::FunctionDeclaration { name, params, body, return_type, .. } => {
+                write!(f, "(func {name} ")?;
+                write_function(f, params, return_type, body)?;
+                write!(f, ")")
+            }
+            Statement::IfStatement { condition, body, else_body, .. } => {
+                write!(f, "(if {condition} ")?;
+                write_block(f, body)?;
+                if let Some(else_body) = else_body {
+                    write!(f, " ")?;
+                    write_block(f, else_body)?;
+                }
+                write!(f, ")")
+            }
+            Statement::While { condition, body, .. } => {
+                write!(f, "(while {condition} ")?;
+                write_block(f, body)?;
+                write!(f, ")")
+            }
+            Statement::For { variable, start, end, body, .. } => {
+                write!(f, "(for {variable} {start} {end} ")?;
+                write_block(f, body)?;
+                write!(f, ")")
+            }
+            Statement::Break { .. } => write!(f, "(break)"),
+            Statement::Continue { .. } => write!(f, "(continue)"),
+            Statement::Return { value: Some(value), .. } => write!(f, "(return {value})"),
+            Statement::Return { value: None, .. } => write!(f, "(return)"),
+            Statement::ExprStatement { expr, .. } => write!(f, "{expr}"),
+        }
+    }
 }
@@ -1,43 +1,171 @@
-use std::collections::HashMap;
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
 
-use voltage_ast::{statements::Statement, FuncParam, Type};
+use voltage_ast::{statements::Statement, FuncParam, Slot, Type};
 
-#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+/// A scope shared between the blocks nested in it and the functions that
+/// capture it.
+pub type Env = Rc<RefCell<Envoirment>>;
+
+/// The variables of one block or call, linked to the scope it is nested in.
+#[derive(Default, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Envoirment {
-    pub global_variables: HashMap<String, Value>,
-    pub r#return: Option<Box<Value>>,
+    /// Variables in order of declaration, so the index of a resolved slot
+    /// finds its variable.
+    pub variables: Vec<Variable>,
+    #[serde(skip)]
+    pub parent: Option<Env>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub value: Value,
+    pub mutable: bool,
 }
 
 impl Envoirment {
-    pub fn set(&mut self, name: String, value: Value) {
-        self.global_variables.insert(name, value);
+    /// A new scope nested in `parent`.
+    pub fn nested(parent: &Env) -> Env {
+        Rc::new(RefCell::new(Envoirment {
+            variables: Vec::new(),
+            parent: Some(Rc::clone(parent)),
+        }))
+    }
+
+    /// Declares a new variable in the next slot of `env`, shadowing any
+    /// with the same name.
+    pub fn declare(env: &Env, name: String, value: Value, mutable: bool) {
+        let value = held_in(env, value);
+        env.borrow_mut().variables.push(Variable {
+            name,
+            value,
+            mutable,
+        });
+    }
+
+    /// The variable `slot` resolves to from this scope.
+    pub fn variable(env: &Env, slot: Slot) -> Option<Variable> {
+        let scope = ancestor(env, slot.depth)?;
+        let scope = scope.borrow();
+        let mut variable = scope.variables.get(slot.index).cloned()?;
+        // Out of its scope, a function keeps the scope alive again
+        if let Value::Function {
+            env: Some(captured @ Captured::Weak(_)),
+            ..
+        } = &mut variable.value
+        {
+            if let Some(scope) = captured.scope() {
+                *captured = Captured::Strong(scope);
+            }
+        }
+        Some(variable)
+    }
+
+    pub fn set(env: &Env, slot: Slot, value: Value) {
+        let scope = ancestor(env, slot.depth).unwrap();
+        let value = held_in(&scope, value);
+        let mut scope = scope.borrow_mut();
+        scope.variables[slot.index].value = value;
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        let variable = self.variables.iter().rev().find(|v| v.name == name)?;
+        Some(&variable.value)
+    }
+}
+
+/// `value` as stored in `env`. A function that captured `env` itself only
+/// refers to it weakly from there, so that the two do not keep each other
+/// alive.
+fn held_in(env: &Env, mut value: Value) -> Value {
+    if let Value::Function {
+        env: Some(captured @ Captured::Strong(_)),
+        ..
+    } = &mut value
+    {
+        if captured.is(env) {
+            *captured = Captured::Weak(Rc::downgrade(env));
+        }
+    }
+    value
+}
+
+/// The scope `depth` levels out from `env`.
+fn ancestor(env: &Env, depth: usize) -> Option<Env> {
+    let mut scope = Rc::clone(env);
+    for _ in 0..depth {
+        let parent = scope.borrow().parent.clone()?;
+        scope = parent;
+    }
+    Some(scope)
+}
+
+// A scope usually holds functions that captured it, so it is printed by
+// the names it declares and compared by identity rather than recursing.
+impl std::fmt::Debug for Envoirment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.variables.iter().map(|variable| &variable.name))
+            .finish()
     }
+}
 
-    pub fn get(&self, name: String) -> Option<&Value> {
-        self.global_variables.get(&name)
+impl PartialEq for Envoirment {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
     }
 }
 
 impl PartialOrd for Envoirment {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        let len: usize = self.global_variables.len();
+        (self == other).then_some(std::cmp::Ordering::Equal)
+    }
+}
 
-        let mut cmp_to: Vec<usize> = Vec::with_capacity(len);
-        cmp_to.fill(0);
+/// The scope a function captured, held weakly while the function is
+/// stored in that same scope.
+#[derive(Clone)]
+pub enum Captured {
+    Strong(Env),
+    Weak(Weak<RefCell<Envoirment>>),
+}
 
-        let mut cmp: Vec<usize> = Vec::new();
+impl Captured {
+    /// The scope, unless it has already been freed.
+    pub fn scope(&self) -> Option<Env> {
+        match self {
+            Captured::Strong(env) => Some(Rc::clone(env)),
+            Captured::Weak(env) => env.upgrade(),
+        }
+    }
 
-        for (k, v) in self.global_variables.clone() {
-            if Some(&v) == other.get(k) {
-                cmp.push(0);
-            }
+    fn is(&self, env: &Env) -> bool {
+        let captured = match self {
+            Captured::Strong(env) => Rc::as_ptr(env),
+            Captured::Weak(env) => env.as_ptr(),
         };
+        std::ptr::eq(captured, Rc::as_ptr(env))
+    }
+}
 
-        if cmp_to.len() == cmp.len(){
-            cmp_to.partial_cmp(&cmp)
-        } else {
-            panic!()
-        }
+impl std::fmt::Debug for Captured {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Captured").field(&self.scope()).finish()
+    }
+}
+
+impl PartialEq for Captured {
+    fn eq(&self, other: &Self) -> bool {
+        other.scope().is_some_and(|env| self.is(&env))
+    }
+}
+
+impl PartialOrd for Captured {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        (self == other).then_some(std::cmp::Ordering::Equal)
     }
 }
 
@@ -49,6 +177,7 @@ pub enum Value {
     },
     Int {
         value: i64,
+        r#type: Type,
     },
     Float {
         value: f64,
@@ -63,12 +192,45 @@ pub enum Value {
         name: String,
         r#type: FunctionType, 
         params: Vec<FuncParam>,
-        body: Vec<Statement>,
-        env: Option<Envoirment>,
+        body: Rc<Vec<Statement>>,
+        /// The scope the function was declared in.
+        #[serde(skip)]
+        env: Option<Captured>,
         return_type: Type,
     },
 }
 
+impl Value {
+    /// Whether both values are of the same type, integers also being of the
+    /// same width.
+    pub fn same_type(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Int { r#type: lhs, .. }, Value::Int { r#type: rhs, .. }) => lhs == rhs,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+/// Prints the value as it would be written in source, e.g. `100i8` or
+/// `'c'`.
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::String { value } => write!(f, "{value:?}"),
+            Value::Int {
+                value,
+                r#type: Type::Int,
+            } => write!(f, "{value}"),
+            Value::Int { value, r#type } => write!(f, "{value}{type}"),
+            Value::Float { value } => write!(f, "{value:?}"),
+            Value::Bool { value } => write!(f, "{value}"),
+            Value::Char { value } => write!(f, "{value:?}"),
+            Value::Function { name, .. } => write!(f, "func {name}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, PartialOrd)]
 pub enum FunctionType {
     Native,
@@ -0,0 +1,142 @@
+use std::fmt;
+
+use voltage_ast::{Operator, Type};
+use voltage_lexer::span::Span;
+
+use super::{Frame, Value, MAX_CALL_DEPTH};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeErrorKind {
+    /// A variable with no slot, from an AST that was not resolved.
+    UndeclaredVariable {
+        name: String,
+    },
+    AssignToImmutable {
+        name: String,
+    },
+    /// An `if` or `while` condition that is not a bool.
+    NotABool {
+        found: Value,
+    },
+    /// A `for` range whose bounds are not integers.
+    InvalidRange {
+        start: Value,
+        end: Value,
+    },
+    NotCallable {
+        found: Value,
+    },
+    /// A binary operator applied to operands it is not defined for.
+    InvalidOperands {
+        op: Operator,
+        lhs: Value,
+        rhs: Value,
+    },
+    /// A prefix operator applied to an operand it is not defined for.
+    InvalidOperand {
+        op: Operator,
+        operand: Value,
+    },
+    /// Integer arithmetic whose result does not fit in `r#type`.
+    Overflow {
+        op: Operator,
+        lhs: i64,
+        rhs: i64,
+        r#type: Type,
+    },
+    DivisionByZero {
+        lhs: i64,
+    },
+    /// A cast of a number that does not fit in the type cast to.
+    CastOutOfRange {
+        value: Value,
+        r#type: Type,
+    },
+    InvalidCast {
+        value: Value,
+        r#type: Type,
+    },
+    /// A call nested more than [`MAX_CALL_DEPTH`] calls deep.
+    StackOverflow,
+}
+
+/// A fault while running, at `span`, with the calls that led to it
+/// innermost last.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: Box<RuntimeErrorKind>,
+    pub span: Span,
+    pub stack: Vec<Frame>,
+}
+
+impl fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeErrorKind::UndeclaredVariable { name } => {
+                write!(f, "no variable '{name}' in scope")
+            }
+            RuntimeErrorKind::AssignToImmutable { name } => {
+                write!(f, "can not assign twice to immutable variable '{name}'")
+            }
+            RuntimeErrorKind::NotABool { found } => {
+                write!(f, "condition must be a bool, found {found}")
+            }
+            RuntimeErrorKind::InvalidRange { start, end } => {
+                write!(f, "a range must be between integers, found {start}..{end}")
+            }
+            RuntimeErrorKind::NotCallable { found } => write!(f, "can not call {found}"),
+            RuntimeErrorKind::InvalidOperands { op, lhs, rhs } => {
+                write!(f, "can not apply '{op}' to {lhs} and {rhs}")
+            }
+            RuntimeErrorKind::InvalidOperand { op, operand } => {
+                write!(f, "can not apply '{op}' to {operand}")
+            }
+            RuntimeErrorKind::Overflow {
+                op,
+                lhs,
+                rhs,
+                r#type,
+            } => write!(f, "{lhs} {op} {rhs} overflows {type}"),
+            RuntimeErrorKind::DivisionByZero { lhs } => write!(f, "division of {lhs} by zero"),
+            RuntimeErrorKind::CastOutOfRange { value, r#type } => {
+                write!(f, "{value} does not fit in {type}")
+            }
+            RuntimeErrorKind::InvalidCast { value, r#type } => {
+                write!(f, "can not cast {value} to {type}")
+            }
+            RuntimeErrorKind::StackOverflow => {
+                write!(
+                    f,
+                    "stack overflow: calls nested more than {MAX_CALL_DEPTH} deep"
+                )
+            }
+        }
+    }
+}
+
+/// Prints the error followed by the stack trace, innermost call first.
+/// A call repeated from the same place, as in a recursion, is printed once
+/// with the number of times it was made.
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[RUNTIME] Error @ {}: {}", self.span, self.kind)?;
+        let mut frames = self.stack.iter().rev().peekable();
+        while let Some(frame) = frames.next() {
+            let mut times = 1;
+            while frames.next_if_eq(&frame).is_some() {
+                times += 1;
+            }
+            write!(
+                f,
+                "\n    in {} called @ {}",
+                frame.function, frame.call_site
+            )?;
+            if times > 1 {
+                write!(f, " (×{times})")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RuntimeError {}
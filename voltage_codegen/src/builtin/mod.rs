@@ -1,322 +1,485 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, rc::Rc};
 
-use voltage_ast::{expressions::Expression, statements::Statement, Operator};
+use voltage_ast::{expressions::Expression, statements::Statement, Mutability, Operator, Type};
+use voltage_lexer::span::Span;
+use voltage_parser::Resolved;
 
-use self::envoirment::{Envoirment, FunctionType, Value};
+pub use self::envoirment::{Captured, Env, Envoirment, FunctionType, Value, Variable};
+pub use self::error::{RuntimeError, RuntimeErrorKind};
 
 mod envoirment;
+mod error;
+
+/// Where execution carries on after a statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlFlow {
+    Next,
+    Break,
+    Continue,
+    /// Leaves the function being called, through any blocks and loops it
+    /// is nested in.
+    Return(Value),
+}
+
+/// How many calls may be nested before running stops with a stack overflow.
+pub const MAX_CALL_DEPTH: usize = 1024;
+
+/// How much native stack a call needs left to run where it was made. Below
+/// that it runs on a new stack instead, so deep recursion never overflows
+/// the thread the engine runs on. A call takes around 15KiB in a debug
+/// build.
+const STACK_RED_ZONE: usize = 256 * 1024;
+
+/// The size of each new stack calls are moved to.
+const STACK_GROWTH: usize = 4 * 1024 * 1024;
+
+/// A call in progress.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub function: String,
+    pub call_site: Span,
+}
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Engine {
-    pub env: Envoirment,
-    pub globals: HashMap<String, Value>,
+    /// The top-level scope, which every other one is nested in.
+    pub env: Env,
+    /// The calls being run, innermost last.
+    #[serde(skip)]
+    pub call_stack: Vec<Frame>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Engine {
     pub fn new() -> Engine {
-        println!("Running with Built in interptor");
         Self {
-            env: Envoirment::default(),
-            globals: HashMap::new(),
+            env: Rc::new(RefCell::new(Envoirment::default())),
+            call_stack: Vec::new(),
         }
     }
 
-    pub fn exectute(&mut self, ast: Vec<Statement>) -> String {
-        for statement in ast {
-            self.run_statement(statement, None)
-        }
+    /// Runs `ast`, stopping at the first runtime error. Each run starts a
+    /// new top-level scope, left in `env` afterwards, since its slots are
+    /// numbered from the first.
+    pub fn exectute(&mut self, ast: Resolved) -> Result<String, RuntimeError> {
+        self.env = Rc::new(RefCell::new(Envoirment::default()));
+        let env = Rc::clone(&self.env);
+        self.run_block(&ast, &env)?;
         #[cfg(feature = "json_abi")]
-        return serde_json::to_string_pretty(&self).unwrap();
+        return Ok(serde_json::to_string_pretty(&self).unwrap());
         #[cfg(not(feature = "json_abi"))]
-        return String::new();
+        return Ok(String::new());
     }
 
-    pub fn run_statement(&mut self, statement: Statement, external_env: Option<&mut Envoirment>) {
+    /// An error at `span` in the call being run.
+    fn error(&self, kind: impl Into<Box<RuntimeErrorKind>>, span: Span) -> RuntimeError {
+        RuntimeError {
+            kind: kind.into(),
+            span,
+            stack: self.call_stack.clone(),
+        }
+    }
+
+    pub fn run_statement(
+        &mut self,
+        statement: &Statement,
+        env: &Env,
+    ) -> Result<ControlFlow, RuntimeError> {
         match statement {
             Statement::VariableDeclaration {
                 name,
                 value,
-                r#type,
+                r#type: _,
+                mutability,
+                ..
             } => {
-                // type check
                 // convert expression to value
-                let value = self.expression_to_value(value, None).unwrap();
-                println!("{value:?}");
-                // instert to global variables
-                match external_env {
-                    Some(env) => env.set(name, value),
-                    None => self.env.set(name, value),
-                }
+                let value = self.expression_to_value(value, env)?;
+                let mutable = *mutability == Mutability::Mutable;
+                Envoirment::declare(env, name.clone(), value, mutable);
             }
-            Statement::FunctionDeclaration {
+            Statement::Assignment {
                 name,
-                params,
-                body,
-                return_type,
+                slot,
+                value,
+                span,
             } => {
-                let env = Envoirment::default();
-                // for statement in body.clone() {
-                //     self.run_statement(statement, Some(&mut env))
-                // }
-                self.globals.insert(
-                    name.clone(),
-                    Value::Function {
-                        name,
-                        params,
-                        r#type: FunctionType::Function,
-                        body,
-                        env: Some(env),
-                        return_type,
-                    },
-                );
+                let value = self.expression_to_value(value, env)?;
+                let variable = slot.and_then(|slot| Envoirment::variable(env, slot));
+                let name = name.clone();
+                match variable {
+                    Some(variable) if !variable.mutable => {
+                        let kind = RuntimeErrorKind::AssignToImmutable { name };
+                        return Err(self.error(kind, *span));
+                    }
+                    Some(_) => Envoirment::set(env, slot.unwrap(), value),
+                    None => {
+                        let kind = RuntimeErrorKind::UndeclaredVariable { name };
+                        return Err(self.error(kind, *span));
+                    }
+                }
             }
+            // Declared when the block it is in starts running
+            Statement::FunctionDeclaration { .. } => {}
             Statement::IfStatement {
-                expr1,
-                cmp_op,
-                expr2,
+                condition,
                 body,
+                else_body,
+                ..
             } => {
-                let mut env = Envoirment {
-                    global_variables: HashMap::new(),
-                    r#return: Some(Box::new(Value::Null)),
+                return self.run_if(condition, body, else_body.as_deref(), env);
+            }
+            Statement::While {
+                condition, body, ..
+            } => return self.run_while(condition, body, env),
+            Statement::For {
+                variable,
+                start,
+                end,
+                body,
+                span,
+            } => return self.run_for(variable, start, end, body, *span, env),
+            Statement::Break { .. } => return Ok(ControlFlow::Break),
+            Statement::Continue { .. } => return Ok(ControlFlow::Continue),
+            Statement::Return { value, .. } => {
+                let value = match value {
+                    Some(value) => self.expression_to_value(value, env)?,
+                    None => Value::Null,
                 };
+                return Ok(ControlFlow::Return(value));
+            }
+            Statement::ExprStatement { expr, .. } => {
+                self.expression_to_value(expr, env)?;
+            }
+        }
 
-                let lhs;
-                let rhs;
+        Ok(ControlFlow::Next)
+    }
 
-                match external_env {
-                    Some(external_env) => {
-                        lhs = self.expression_to_value(expr1, Some(external_env));
-                        rhs = self.expression_to_value(expr2, Some(external_env));
-                    }
-                    None => {
-                        lhs = self.expression_to_value(expr1, None);
-                        rhs = self.expression_to_value(expr2, None);
-                    }
-                }
+    // The statements and expressions that run others are each in a method
+    // of their own, to keep the frames of `run_statement` and
+    // `expression_to_value`, which every call recurses through, small.
 
-                match cmp_op {
-                    voltage_ast::CmpOperators::Equal => {
-                        if lhs == rhs {
-                            for statement in body {
-                                self.run_statement(statement, Some(&mut env))
-                            }
-                        }
-                    }
-                    voltage_ast::CmpOperators::NotEqual => {
-                        if lhs != rhs {
-                            for statement in body {
-                                self.run_statement(statement, Some(&mut env))
-                            }
-                        }
-                    }
-                    voltage_ast::CmpOperators::GreaterThen => {
-                        if lhs > rhs {
-                            for statement in body {
-                                self.run_statement(statement, Some(&mut env))
-                            }
-                        }
-                    }
-                    voltage_ast::CmpOperators::LessThen => {
-                        if lhs < rhs {
-                            for statement in body {
-                                self.run_statement(statement, Some(&mut env))
-                            }
-                        }
-                    }
-                    voltage_ast::CmpOperators::GreaterThenOrEqual => {
-                        if lhs != rhs {
-                            for statement in body {
-                                self.run_statement(statement, Some(&mut env))
-                            }
-                        }
-                    }
-                    voltage_ast::CmpOperators::LessThenOrEqual => {
-                        if lhs != rhs {
-                            for statement in body {
-                                self.run_statement(statement, Some(&mut env))
-                            }
-                        }
-                    }
-                }
+    fn run_if(
+        &mut self,
+        condition: &Expression,
+        body: &[Statement],
+        else_body: Option<&[Statement]>,
+        env: &Env,
+    ) -> Result<ControlFlow, RuntimeError> {
+        let branch = if self.condition(condition, env)? {
+            body
+        } else {
+            match else_body {
+                Some(else_body) => else_body,
+                None => return Ok(ControlFlow::Next),
+            }
+        };
+        let scope = Envoirment::nested(env);
+        self.run_block(branch, &scope)
+    }
+
+    fn run_while(
+        &mut self,
+        condition: &Expression,
+        body: &[Statement],
+        env: &Env,
+    ) -> Result<ControlFlow, RuntimeError> {
+        while self.condition(condition, env)? {
+            let scope = Envoirment::nested(env);
+            match self.run_block(body, &scope)? {
+                ControlFlow::Break => break,
+                flow @ ControlFlow::Return(_) => return Ok(flow),
+                _ => {}
+            }
+        }
+        Ok(ControlFlow::Next)
+    }
+
+    fn run_for(
+        &mut self,
+        variable: &str,
+        start: &Expression,
+        end: &Expression,
+        body: &[Statement],
+        span: Span,
+        env: &Env,
+    ) -> Result<ControlFlow, RuntimeError> {
+        let start = self.expression_to_value(start, env)?;
+        let end = self.expression_to_value(end, env)?;
+        let (
+            Value::Int {
+                value: from,
+                r#type,
+            },
+            Value::Int { value: to, .. },
+        ) = (&start, &end)
+        else {
+            let kind = RuntimeErrorKind::InvalidRange { start, end };
+            return Err(self.error(kind, span));
+        };
 
-                println!("{:?} {:?}", lhs, rhs);
+        for i in *from..*to {
+            // The loop variable only lives in the body
+            let scope = Envoirment::nested(env);
+            let value = Value::Int {
+                value: i,
+                r#type: r#type.clone(),
+            };
+            Envoirment::declare(&scope, variable.to_string(), value, false);
+            match self.run_block(body, &scope)? {
+                ControlFlow::Break => break,
+                flow @ ControlFlow::Return(_) => return Ok(flow),
+                _ => {}
             }
-            Statement::Return { value } => {
-                let env = external_env.unwrap();
-                let ret = self.expression_to_value(value, Some(env)).unwrap();
+        }
+        Ok(ControlFlow::Next)
+    }
+
+    /// Evaluates the condition of an `if` or `while`.
+    fn condition(&mut self, condition: &Expression, env: &Env) -> Result<bool, RuntimeError> {
+        let span = condition.span();
+        match self.expression_to_value(condition, env)? {
+            Value::Bool { value } => Ok(value),
+            found => Err(self.error(RuntimeErrorKind::NotABool { found }, span)),
+        }
+    }
 
-                env.r#return = Some(Box::new(ret));
+    /// Runs `body` in `env`, stopping early at a `break`, `continue` or
+    /// `return`.
+    fn run_block(&mut self, body: &[Statement], env: &Env) -> Result<ControlFlow, RuntimeError> {
+        // Functions are declared before anything runs, in the first slots of
+        // the block, so that they can call each other in any order
+        for statement in body {
+            if let Statement::FunctionDeclaration {
+                name,
+                params,
+                body,
+                return_type,
+                ..
+            } = statement
+            {
+                let function = Value::Function {
+                    name: name.clone(),
+                    params: params.clone(),
+                    r#type: FunctionType::Function,
+                    body: Rc::new(body.clone()),
+                    env: Some(Captured::Strong(Rc::clone(env))),
+                    return_type: return_type.clone(),
+                };
+                Envoirment::declare(env, name.clone(), function, false);
             }
-            Statement::ExprStatement { expr } => {
-                self.expression_to_value(expr, external_env);
+        }
+        for statement in body {
+            let flow = self.run_statement(statement, env)?;
+            if flow != ControlFlow::Next {
+                return Ok(flow);
             }
         }
+        Ok(ControlFlow::Next)
     }
 
     pub fn expression_to_value(
         &mut self,
-        expr: Expression,
-        external_env: Option<&mut Envoirment>,
-    ) -> Option<Value> {
-        let value = match expr {
-            voltage_ast::expressions::Expression::StringLiteral { val } => {
-                Value::String { value: val }
+        expr: &Expression,
+        env: &Env,
+    ) -> Result<Value, RuntimeError> {
+        let span = expr.span();
+        match expr {
+            voltage_ast::expressions::Expression::StringLiteral { val, .. } => {
+                Ok(Value::String { value: val.clone() })
             }
-            voltage_ast::expressions::Expression::IntLiteral { val } => Value::Int { value: val },
-            voltage_ast::expressions::Expression::BooleanLiteral { val } => {
-                Value::Bool { value: val }
+            voltage_ast::expressions::Expression::IntLiteral { val, r#type, .. } => {
+                Ok(Value::Int {
+                    value: *val,
+                    r#type: r#type.clone(),
+                })
             }
-            voltage_ast::expressions::Expression::FloatLiteral { val } => {
-                Value::Float { value: val }
+            voltage_ast::expressions::Expression::BooleanLiteral { val, .. } => {
+                Ok(Value::Bool { value: *val })
             }
-            voltage_ast::expressions::Expression::CharLiteral { val } => Value::Char { value: val },
-            voltage_ast::expressions::Expression::FunctionCall { name, params } => {
-                let func_params = params;
-
-                let handle_get_value =
-                    || match self.expression_to_value(*name.clone(), external_env) {
-                        Some(value) => value,
-                        None => self
-                            .expression_to_value(
-                                *name,
-                                Some(&mut Envoirment {
-                                    global_variables: self.globals.clone(),
-                                    r#return: None,
-                                }),
-                            )
-                            .unwrap(),
-                    };
-
-                if let Value::Function {
-                    name,
-                    params,
-                    body,
-                    mut env,
-                    return_type,
-                    r#type,
-                } = handle_get_value()
-                {
-                    if name.ends_with("$") && matches!(r#type, FunctionType::Native) {
-                        return Some(Value::Null);
-                    } else {
-
-                        for (k, _) in env.clone().unwrap().global_variables {
-                            env.as_mut().unwrap().global_variables.remove(&k).unwrap();
-                        }
-
-                        match env.as_mut().unwrap().r#return {
-                            Some(_) => env.as_mut().unwrap().r#return = None,
-                            None => env.as_mut().unwrap().r#return = None,
-                        }
-
-                        let mut true_params: Vec<Value> = vec![];
-                        for param in func_params {
-                            true_params
-                                .push(self.expression_to_value(param, env.as_mut()).unwrap());
-                        }
-
-                        let mut idx = 0;
-                        for param in params {
-                            env.as_mut()
-                                .unwrap()
-                                .set(param.name, true_params[idx].clone());
-
-                            idx += 1;
-                        }
-
-                        for statement in body {
-                            self.run_statement(statement, env.as_mut());
-                        }
-
-                        println!("{:?}", env.clone());
-
-                        for (k, _) in env.clone().unwrap().global_variables {
-                            env.as_mut().unwrap().global_variables.remove(&k).unwrap();
-                        }
-
-                        println!("{:?}", env.clone());
-
-                        return Some(*env.unwrap().r#return.unwrap());
-                    }
-                };
-
-                Value::Null
+            voltage_ast::expressions::Expression::FloatLiteral { val, .. } => {
+                Ok(Value::Float { value: *val })
             }
-            voltage_ast::expressions::Expression::BinaryExpr { op, lhs, rhs } => {
-                self.run_binary_op(lhs, op, rhs, external_env)
+            voltage_ast::expressions::Expression::CharLiteral { val, .. } => {
+                Ok(Value::Char { value: *val })
             }
-            voltage_ast::expressions::Expression::UnaryExpr { op, child } => todo!(),
-            voltage_ast::expressions::Expression::Identifier { val } => {
-                match self.env.get(val.clone()).cloned() {
-                    Some(value) => value,
-                    None => match external_env {
-                        Some(env) => match env.get(val.clone()).cloned() {
-                            Some(val) => val,
-                            None => panic!("Variable {} not found", val.clone()),
-                        },
-                        None => return None,
-                    },
+            voltage_ast::expressions::Expression::FunctionCall { name, params, .. } => {
+                self.run_call(name, params, span, env)
+            }
+            voltage_ast::expressions::Expression::Function {
+                params,
+                return_type,
+                body,
+                ..
+            } => Ok(Value::Function {
+                name: String::from("<anonymous>"),
+                r#type: FunctionType::Function,
+                params: params.clone(),
+                body: Rc::new(body.clone()),
+                env: Some(Captured::Strong(Rc::clone(env))),
+                return_type: return_type.clone(),
+            }),
+            voltage_ast::expressions::Expression::BinaryExpr { op, lhs, rhs, .. } => {
+                self.run_binary_op(lhs, op.clone(), rhs, env)
+            }
+            voltage_ast::expressions::Expression::UnaryExpr { op, child, .. } => {
+                self.run_unary_op(op.clone(), child, span, env)
+            }
+            voltage_ast::expressions::Expression::Cast { expr, r#type, .. } => {
+                let value = self.expression_to_value(expr, env)?;
+                cast(value, r#type).map_err(|kind| self.error(kind, span))
+            }
+            voltage_ast::expressions::Expression::Identifier { val, slot, .. } => {
+                match slot.and_then(|slot| Envoirment::variable(env, slot)) {
+                    Some(variable) => Ok(variable.value),
+                    None => {
+                        let name = val.clone();
+                        let kind = RuntimeErrorKind::UndeclaredVariable { name };
+                        Err(self.error(kind, span))
+                    }
                 }
             }
+        }
+    }
+
+    /// Evaluates the function called at `call_site`, then its arguments in
+    /// order, and calls it.
+    fn run_call(
+        &mut self,
+        name: &Expression,
+        params: &[Expression],
+        call_site: Span,
+        env: &Env,
+    ) -> Result<Value, RuntimeError> {
+        let function = self.expression_to_value(name, env)?;
+        let args = params
+            .iter()
+            .map(|param| self.expression_to_value(param, env))
+            .collect::<Result<_, _>>()?;
+        self.call(function, args, call_site)
+    }
+
+    /// Calls `function` with `args` from `call_site`, returning what it
+    /// returns.
+    fn call(
+        &mut self,
+        function: Value,
+        args: Vec<Value>,
+        call_site: Span,
+    ) -> Result<Value, RuntimeError> {
+        let Value::Function {
+            name,
+            params,
+            body,
+            env,
+            r#type,
+            ..
+        } = function
+        else {
+            let kind = RuntimeErrorKind::NotCallable { found: function };
+            return Err(self.error(kind, call_site));
         };
+        if name.ends_with('$') && matches!(r#type, FunctionType::Native) {
+            return Ok(Value::Null);
+        }
+
+        if self.call_stack.len() >= MAX_CALL_DEPTH {
+            return Err(self.error(RuntimeErrorKind::StackOverflow, call_site));
+        }
 
-        Some(value)
+        // Each call gets its own frame, with a scope for its parameters
+        // nested in the one the function was declared in
+        let scope = match env.and_then(|env| env.scope()) {
+            Some(env) => Envoirment::nested(&env),
+            None => Envoirment::nested(&self.env),
+        };
+        for (param, value) in params.into_iter().zip(args) {
+            Envoirment::declare(&scope, param.name, value, false);
+        }
+        self.call_stack.push(Frame {
+            function: name,
+            call_site,
+        });
+        let flow = stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || {
+            self.run_block(&body, &scope)
+        });
+        self.call_stack.pop();
+
+        match flow? {
+            ControlFlow::Return(value) => Ok(value),
+            _ => Ok(Value::Null),
+        }
     }
 
     pub fn run_binary_op(
+        &mut self,
+        lhs: &Expression,
+        op: Operator,
+        rhs: &Expression,
+        env: &Env,
+    ) -> Result<Value, RuntimeError> {
+        let span = lhs.span().to(rhs.span());
+        let lhs = self.expression_to_value(lhs, env)?;
+        if let Some(value) = short_circuit(&op, &lhs) {
+            return Ok(value);
+        }
+        let rhs = self.expression_to_value(rhs, env)?;
+        self.apply_binary_op(lhs, op, rhs)
+            .map_err(|kind| self.error(kind, span))
+    }
+
+    fn run_unary_op(
+        &mut self,
+        op: Operator,
+        child: &Expression,
+        span: Span,
+        env: &Env,
+    ) -> Result<Value, RuntimeError> {
+        let child = self.expression_to_value(child, env)?;
+        self.apply_unary_op(op, child)
+            .map_err(|kind| self.error(kind, span))
+    }
+
+    pub fn apply_unary_op(
         &self,
-        lhs: Box<Expression>,
         op: Operator,
-        rhs: Box<Expression>,
-        external_env: Option<&mut Envoirment>,
-    ) -> Value {
-        let lhs = match unbox(lhs) {
-            Expression::IntLiteral { val } => Value::Int { value: val },
-            Expression::FloatLiteral { val } => Value::Float { value: val },
-            Expression::BinaryExpr { op, lhs, rhs } => self.run_binary_op(lhs, op, rhs, None),
-            voltage_ast::expressions::Expression::Identifier { val } => {
-                match self.env.get(val.clone()).cloned() {
-                    Some(value) => value,
-                    None => match external_env.as_ref().unwrap().get(val.clone()) {
-                        Some(value) => value,
-                        None => panic!("No variable {val} found"),
-                    }
-                    .clone(),
-                }
+        value: Value,
+    ) -> Result<Value, Box<RuntimeErrorKind>> {
+        match (&op, &value) {
+            (Operator::Not, Value::Bool { value }) => Ok(Value::Bool { value: !value }),
+            (Operator::Minus, Value::Int { value, r#type }) => {
+                int_arithmetic(&op, 0, *value, r#type)
             }
-            x => panic!("Can not add {:?}", x),
-        };
-        let rhs = match unbox(rhs) {
-            Expression::IntLiteral { val } => Value::Int { value: val },
-            Expression::FloatLiteral { val } => Value::Float { value: val },
-            Expression::BinaryExpr { op, lhs, rhs } => self.run_binary_op(lhs, op, rhs, None),
-            voltage_ast::expressions::Expression::Identifier { val } => {
-                match self.env.get(val.clone()).cloned() {
-                    Some(value) => value,
-                    None => match external_env.cloned().unwrap().get(val.clone()) {
-                        Some(value) => value,
-                        None => panic!("No variable {val} found"),
-                    }
-                    .clone(),
-                }
-            }
-            x => panic!("Can not add {:?}", x),
-        };
+            (Operator::Minus, Value::Float { value }) => Ok(Value::Float { value: -value }),
+            (Operator::Plus, Value::Int { .. } | Value::Float { .. }) => Ok(value),
+            _ => Err(Box::new(RuntimeErrorKind::InvalidOperand {
+                op,
+                operand: value,
+            })),
+        }
+    }
 
+    pub fn apply_binary_op(
+        &self,
+        lhs: Value,
+        op: Operator,
+        rhs: Value,
+    ) -> Result<Value, Box<RuntimeErrorKind>> {
         match op {
             // Add
             voltage_ast::Operator::Plus => {
-                if matches!(lhs, Value::Int { .. }) && matches!(rhs, Value::Int { .. }) {
-                    let mut x = 0;
-                    let mut y = 0;
-                    if let Value::Int { value } = lhs {
-                        x = value
-                    }
-                    if let Value::Int { value } = rhs {
-                        y = value;
+                if let (Value::Int { value: x, r#type }, Value::Int { value: y, .. }) = (&lhs, &rhs)
+                {
+                    if lhs.same_type(&rhs) {
+                        return int_arithmetic(&op, *x, *y, r#type);
                     }
-                    return Value::Int { value: x + y };
+                    Err(Box::new(RuntimeErrorKind::InvalidOperands { op, lhs, rhs }))
                 } else if matches!(lhs, Value::Float { .. }) && matches!(rhs, Value::Float { .. }) {
                     let mut x = 0.0;
                     let mut y = 0.0;
@@ -326,23 +489,19 @@ impl Engine {
                     if let Value::Float { value } = rhs {
                         y = value;
                     }
-                    return Value::Float { value: x + y };
+                    Ok(Value::Float { value: x + y })
                 } else {
-                    panic!("Can not add {:?} with {:?}", lhs, rhs)
+                    Err(Box::new(RuntimeErrorKind::InvalidOperands { op, lhs, rhs }))
                 }
             }
             // Subtract
             voltage_ast::Operator::Minus => {
-                if matches!(lhs, Value::Int { .. }) && matches!(rhs, Value::Int { .. }) {
-                    let mut x = 0;
-                    let mut y = 0;
-                    if let Value::Int { value } = lhs {
-                        x = value
-                    }
-                    if let Value::Int { value } = rhs {
-                        y = value;
+                if let (Value::Int { value: x, r#type }, Value::Int { value: y, .. }) = (&lhs, &rhs)
+                {
+                    if lhs.same_type(&rhs) {
+                        return int_arithmetic(&op, *x, *y, r#type);
                     }
-                    return Value::Int { value: x - y };
+                    Err(Box::new(RuntimeErrorKind::InvalidOperands { op, lhs, rhs }))
                 } else if matches!(lhs, Value::Float { .. }) && matches!(rhs, Value::Float { .. }) {
                     let mut x = 0.0;
                     let mut y = 0.0;
@@ -352,23 +511,19 @@ impl Engine {
                     if let Value::Float { value } = rhs {
                         y = value;
                     }
-                    return Value::Float { value: x - y };
+                    Ok(Value::Float { value: x - y })
                 } else {
-                    panic!("Can not subtract {:?} with {:?}", lhs, rhs)
+                    Err(Box::new(RuntimeErrorKind::InvalidOperands { op, lhs, rhs }))
                 }
             }
             // Multiply
             voltage_ast::Operator::Multiplication => {
-                if matches!(lhs, Value::Int { .. }) && matches!(rhs, Value::Int { .. }) {
-                    let mut x = 0;
-                    let mut y = 0;
-                    if let Value::Int { value } = lhs {
-                        x = value
-                    }
-                    if let Value::Int { value } = rhs {
-                        y = value;
+                if let (Value::Int { value: x, r#type }, Value::Int { value: y, .. }) = (&lhs, &rhs)
+                {
+                    if lhs.same_type(&rhs) {
+                        return int_arithmetic(&op, *x, *y, r#type);
                     }
-                    return Value::Int { value: x * y };
+                    Err(Box::new(RuntimeErrorKind::InvalidOperands { op, lhs, rhs }))
                 } else if matches!(lhs, Value::Float { .. }) && matches!(rhs, Value::Float { .. }) {
                     let mut x = 0.0;
                     let mut y = 0.0;
@@ -378,23 +533,19 @@ impl Engine {
                     if let Value::Float { value } = rhs {
                         y = value;
                     }
-                    return Value::Float { value: x * y };
+                    Ok(Value::Float { value: x * y })
                 } else {
-                    panic!("Can not multiply {:?} with {:?}", lhs, rhs)
+                    Err(Box::new(RuntimeErrorKind::InvalidOperands { op, lhs, rhs }))
                 }
             }
             // Divide
             voltage_ast::Operator::Division => {
-                if matches!(lhs, Value::Int { .. }) && matches!(rhs, Value::Int { .. }) {
-                    let mut x = 0;
-                    let mut y = 0;
-                    if let Value::Int { value } = lhs {
-                        x = value
-                    }
-                    if let Value::Int { value } = rhs {
-                        y = value;
+                if let (Value::Int { value: x, r#type }, Value::Int { value: y, .. }) = (&lhs, &rhs)
+                {
+                    if lhs.same_type(&rhs) {
+                        return int_arithmetic(&op, *x, *y, r#type);
                     }
-                    return Value::Int { value: x / y };
+                    Err(Box::new(RuntimeErrorKind::InvalidOperands { op, lhs, rhs }))
                 } else if matches!(lhs, Value::Float { .. }) && matches!(rhs, Value::Float { .. }) {
                     let mut x = 0.0;
                     let mut y = 0.0;
@@ -404,15 +555,151 @@ impl Engine {
                     if let Value::Float { value } = rhs {
                         y = value;
                     }
-                    return Value::Float { value: x / y };
+                    Ok(Value::Float { value: x / y })
                 } else {
-                    panic!("Can not divide {:?} with {:?}", lhs, rhs)
+                    Err(Box::new(RuntimeErrorKind::InvalidOperands { op, lhs, rhs }))
                 }
             }
+            voltage_ast::Operator::WrappingPlus
+            | voltage_ast::Operator::WrappingMinus
+            | voltage_ast::Operator::WrappingMultiplication => match (&lhs, &rhs) {
+                (Value::Int { value: x, r#type }, Value::Int { value: y, .. })
+                    if lhs.same_type(&rhs) =>
+                {
+                    int_arithmetic(&op, *x, *y, r#type)
+                }
+                _ => Err(Box::new(RuntimeErrorKind::InvalidOperands { op, lhs, rhs })),
+            },
+            // Logical
+            voltage_ast::Operator::And | voltage_ast::Operator::Or => match (&lhs, &rhs) {
+                (Value::Bool { value: x }, Value::Bool { value: y }) => Ok(Value::Bool {
+                    value: if op == Operator::And {
+                        *x && *y
+                    } else {
+                        *x || *y
+                    },
+                }),
+                _ => Err(Box::new(RuntimeErrorKind::InvalidOperands { op, lhs, rhs })),
+            },
+            voltage_ast::Operator::Not => {
+                Err(Box::new(RuntimeErrorKind::InvalidOperands { op, lhs, rhs }))
+            }
+            // Compare
+            voltage_ast::Operator::Equal
+            | voltage_ast::Operator::NotEqual
+            | voltage_ast::Operator::GreaterThen
+            | voltage_ast::Operator::LessThen
+            | voltage_ast::Operator::GreaterThenOrEqual
+            | voltage_ast::Operator::LessThenOrEqual => {
+                if !lhs.same_type(&rhs) {
+                    return Err(Box::new(RuntimeErrorKind::InvalidOperands { op, lhs, rhs }));
+                }
+                let value = match op {
+                    voltage_ast::Operator::Equal => lhs == rhs,
+                    voltage_ast::Operator::NotEqual => lhs != rhs,
+                    voltage_ast::Operator::GreaterThen => lhs > rhs,
+                    voltage_ast::Operator::LessThen => lhs < rhs,
+                    voltage_ast::Operator::GreaterThenOrEqual => lhs >= rhs,
+                    _ => lhs <= rhs,
+                };
+                Ok(Value::Bool { value })
+            }
+        }
+    }
+}
+
+/// Applies an arithmetic operator to two integers of type `r#type`,
+/// failing if the result does not fit in it, unless the operator is one of
+/// the wrapping ones, `+%`, `-%` and `*%`, which wrap around instead.
+fn int_arithmetic(
+    op: &Operator,
+    lhs: i64,
+    rhs: i64,
+    r#type: &Type,
+) -> Result<Value, Box<RuntimeErrorKind>> {
+    let (x, y) = (i128::from(lhs), i128::from(rhs));
+    let value = match op {
+        Operator::Plus | Operator::WrappingPlus => x + y,
+        Operator::Minus | Operator::WrappingMinus => x - y,
+        Operator::Multiplication | Operator::WrappingMultiplication => x * y,
+        Operator::Division if y == 0 => {
+            return Err(Box::new(RuntimeErrorKind::DivisionByZero { lhs }))
+        }
+        Operator::Division => x / y,
+        _ => {
+            return Err(Box::new(RuntimeErrorKind::InvalidOperands {
+                op: op.clone(),
+                lhs: Value::Int {
+                    value: lhs,
+                    r#type: r#type.clone(),
+                },
+                rhs: Value::Int {
+                    value: rhs,
+                    r#type: r#type.clone(),
+                },
+            }))
+        }
+    };
+
+    let range = r#type.int_range().unwrap();
+    let value = match op {
+        Operator::WrappingPlus | Operator::WrappingMinus | Operator::WrappingMultiplication => {
+            let modulus = range.end() - range.start() + 1;
+            (value - range.start()).rem_euclid(modulus) + range.start()
         }
+        _ if range.contains(&value) => value,
+        _ => {
+            return Err(Box::new(RuntimeErrorKind::Overflow {
+                op: op.clone(),
+                lhs,
+                rhs,
+                r#type: r#type.clone(),
+            }))
+        }
+    };
+    Ok(Value::Int {
+        value: value as i64,
+        r#type: r#type.clone(),
+    })
+}
+
+/// Converts `value` to `r#type` for `value as r#type`, failing if it does
+/// not fit.
+fn cast(value: Value, r#type: &Type) -> Result<Value, Box<RuntimeErrorKind>> {
+    let int = match (&value, r#type) {
+        (Value::Int { value, .. }, Type::Float) => {
+            return Ok(Value::Float {
+                value: *value as f64,
+            })
+        }
+        (Value::Float { .. }, Type::Float) => return Ok(value),
+        (Value::Int { value, .. }, _) => Some(i128::from(*value)),
+        // Truncates towards zero, NaN never fits
+        (Value::Float { value }, _) if !value.is_nan() => Some(value.trunc() as i128),
+        _ => None,
+    };
+    match (int, r#type.int_range()) {
+        (Some(int), Some(range)) if range.contains(&int) => Ok(Value::Int {
+            value: int as i64,
+            r#type: r#type.clone(),
+        }),
+        (Some(_), Some(_)) => Err(Box::new(RuntimeErrorKind::CastOutOfRange {
+            value,
+            r#type: r#type.clone(),
+        })),
+        _ => Err(Box::new(RuntimeErrorKind::InvalidCast {
+            value,
+            r#type: r#type.clone(),
+        })),
     }
 }
 
-fn unbox<T>(value: Box<T>) -> T {
-    *value
+/// The result of `lhs and rhs` or `lhs or rhs` when `lhs` alone decides
+/// it, in which case `rhs` must not be evaluated.
+fn short_circuit(op: &Operator, lhs: &Value) -> Option<Value> {
+    match (op, lhs) {
+        (Operator::And, Value::Bool { value: false })
+        | (Operator::Or, Value::Bool { value: true }) => Some(lhs.clone()),
+        _ => None,
+    }
 }
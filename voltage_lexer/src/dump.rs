@@ -0,0 +1,47 @@
+//! Stable text and JSON renderings of a token stream, used by the
+//! `voltage tokens` command and the lexer's golden tests.
+
+use std::fmt::Write;
+
+use crate::{
+    span::Span,
+    tokens::{SpannedToken, TokenKind},
+};
+
+#[derive(serde::Serialize)]
+struct TokenEntry<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    span: Span,
+}
+
+/// One token per line: `<offsets> <line:col span> <kind> <quoted text>`.
+pub fn to_text(source: &str, tokens: &[SpannedToken]) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        writeln!(
+            out,
+            "{}..{} {} {} {:?}",
+            token.span.start.offset,
+            token.span.end.offset,
+            token.span,
+            token.token.kind(),
+            token.text(source)
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// A JSON array of `{ "kind", "text", "span" }` objects.
+pub fn to_json(source: &str, tokens: &[SpannedToken]) -> String {
+    let entries: Vec<TokenEntry> = tokens
+        .iter()
+        .map(|token| TokenEntry {
+            kind: token.token.kind(),
+            text: token.text(source),
+            span: token.span,
+        })
+        .collect();
+    serde_json::to_string_pretty(&entries).unwrap()
+}
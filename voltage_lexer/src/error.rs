@@ -0,0 +1,46 @@
+use std::fmt;
+
+use crate::span::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexErrorKind {
+    UnknownCharacter(char),
+    UnterminatedString,
+    UnterminatedChar,
+    EmptyChar,
+    UnterminatedComment,
+    MalformedNumber(String),
+    InvalidEscape(char),
+    InvalidUnicodeEscape(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Span,
+}
+
+impl fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexErrorKind::UnknownCharacter(ch) => write!(f, "unknown character {ch:?}"),
+            LexErrorKind::UnterminatedString => write!(f, "unterminated string literal"),
+            LexErrorKind::UnterminatedChar => write!(f, "unterminated char literal"),
+            LexErrorKind::EmptyChar => write!(f, "empty char literal"),
+            LexErrorKind::UnterminatedComment => write!(f, "unterminated block comment"),
+            LexErrorKind::MalformedNumber(number) => write!(f, "malformed number '{number}'"),
+            LexErrorKind::InvalidEscape(ch) => write!(f, "invalid escape sequence '\\{ch}'"),
+            LexErrorKind::InvalidUnicodeEscape(digits) => {
+                write!(f, "invalid unicode escape '\\u{{{digits}}}'")
+            }
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[LEXER] Error @ {}: {}", self.span, self.kind)
+    }
+}
+
+impl std::error::Error for LexError {}
@@ -1,153 +1,398 @@
-use tokens::Token;
+use std::borrow::Cow;
 
+use error::{LexError, LexErrorKind};
+use span::{Location, Span};
+use tokens::{SpannedToken, Token};
+use unicode_xid::UnicodeXID;
+
+pub mod dump;
+pub mod error;
+pub mod span;
 pub mod tokens;
 
-#[derive(Debug)]
-pub struct Lexer {
-    pub input: Vec<char>,
-    pub position: usize,
-    pub line: usize,
-    pub read_position: usize,
+/// Identifiers start with a Unicode `XID_Start` character or `_`.
+pub fn is_identifier_start(ch: char) -> bool {
+    ch == '_' || ch.is_xid_start()
+}
+
+/// Identifiers continue with Unicode `XID_Continue` characters, which
+/// include digits and `_`.
+pub fn is_identifier_continue(ch: char) -> bool {
+    ch.is_xid_continue()
+}
+
+/// Lexes a source string without copying it. Tokens borrow identifiers,
+/// numbers, comments and escape-free strings straight from the input, and
+/// the lexer can be driven as an iterator so a file is lexed one token at
+/// a time.
+#[derive(Debug, Clone)]
+pub struct Lexer<'src> {
+    pub input: &'src str,
     pub ch: char,
+    pub location: Location,
+    /// Emit whitespace and comments as tokens instead of discarding them.
+    pub trivia: bool,
 }
 
-impl Lexer {
-    pub fn new(source: Vec<char>) -> Self {
+impl<'src> Lexer<'src> {
+    pub fn new(source: &'src str) -> Self {
         Self {
             input: source,
-            position: 0,
-            line: 0,
-            read_position: 0,
-            ch: ' ',
+            ch: source.chars().next().unwrap_or('\0'),
+            location: Location::default(),
+            trivia: false,
         }
     }
 
-    pub fn lex(&mut self) -> Vec<tokens::Token> {
+    /// A lexer that also emits whitespace and comments as trivia tokens, for
+    /// tools such as formatters that need to preserve them. Concatenating
+    /// the text of every token reproduces the source exactly.
+    pub fn with_trivia(source: &'src str) -> Self {
+        Self {
+            trivia: true,
+            ..Self::new(source)
+        }
+    }
+
+    /// Lexes the whole input. Lexing carries on past errors so every
+    /// problem in the file is reported at once.
+    pub fn lex(&mut self) -> Result<Vec<SpannedToken<'src>>, Vec<LexError>> {
         let mut tokens = vec![];
-        self.read_char();
-        loop {
-            let token = self.next_token();
-            if token == tokens::Token::EOF {
-                break;
-            } else {
-                if token != tokens::Token::Whitespace && token != tokens::Token::Unkown {
-                    tokens.push(token);
-                }
+        let mut errors = vec![];
+        for token in self.by_ref() {
+            match token {
+                Ok(token) => tokens.push(token),
+                Err(error) => errors.push(error),
             }
         }
-        tokens
-    }
 
-    pub fn read_char(&mut self) {
-        if self.read_position >= self.input.len() {
-            self.ch = '\0';
+        if errors.is_empty() {
+            Ok(tokens)
         } else {
-            self.ch = self.input[self.read_position];
+            Err(errors)
         }
-        self.position = self.read_position;
-        self.read_position = self.read_position + 1;
     }
 
-    pub fn read_char_back(&mut self) {
-        if self.read_position >= self.input.len() {
-            self.ch = '\0';
+    pub fn read_char(&mut self) {
+        if self.is_eof() {
+            return;
+        }
+
+        self.location.offset += self.ch.len_utf8();
+        if self.ch == '\n' {
+            self.location.line += 1;
+            self.location.column = 1;
         } else {
-            self.ch = self.input[self.read_position - 2];
+            self.location.column += 1;
         }
-        self.position = self.read_position;
-        self.read_position = self.read_position - 1;
+        self.ch = self.peek_nth(0);
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.location.offset >= self.input.len()
+    }
+
+    /// The character `n` places after the current one, or `'\0'` past the
+    /// end of input.
+    pub fn peek_nth(&self, n: usize) -> char {
+        self.input[self.location.offset..]
+            .chars()
+            .nth(n)
+            .unwrap_or('\0')
+    }
+
+    pub fn peek_char(&self) -> char {
+        self.peek_nth(1)
+    }
+
+    /// The input from `start` up to the current character.
+    fn slice_from(&self, start: usize) -> &'src str {
+        let input = self.input;
+        &input[start..self.location.offset]
     }
 
     pub fn skip_whitespace(&mut self) {
-        let ch = self.ch;
-        if ch.is_whitespace() {
+        while self.ch.is_whitespace() {
             self.read_char();
         }
     }
 
-    fn token_match(&mut self) -> tokens::Token {
-        let read_identifier = |l: &mut Lexer| -> Vec<char> {
-            let position = l.position;
-            while l.position < l.input.len() && l.ch.is_alphanumeric() {
-                l.read_char();
+    /// Reads the escape sequence starting at the current `\` and returns
+    /// the character it stands for.
+    fn read_escape(&mut self) -> Result<char, LexErrorKind> {
+        self.read_char();
+        if self.is_eof() {
+            return Err(LexErrorKind::InvalidEscape('\0'));
+        }
+
+        let escaped = match self.ch {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '\'' => '\'',
+            'u' => {
+                self.read_char();
+                let position = self.location.offset;
+                if self.ch != '{' {
+                    return Err(LexErrorKind::InvalidUnicodeEscape(String::new()));
+                }
+                self.read_char();
+                while self.ch.is_ascii_hexdigit() {
+                    self.read_char();
+                }
+                let digits = self.slice_from(position + 1).to_string();
+                if self.ch != '}' {
+                    return Err(LexErrorKind::InvalidUnicodeEscape(digits));
+                }
+                self.read_char();
+
+                return u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() <= 6)
+                    .and_then(char::from_u32)
+                    .ok_or(LexErrorKind::InvalidUnicodeEscape(digits));
+            }
+            ch => {
+                self.read_char();
+                return Err(LexErrorKind::InvalidEscape(ch));
             }
-            l.input[position..l.position].to_vec()
         };
+        self.read_char();
 
-        let read_number = |l: &mut Lexer| -> Vec<char> {
-            let position = l.position;
+        Ok(escaped)
+    }
 
-            while l.position < l.input.len() {
-                if l.ch.is_numeric() || l.ch == '.' {
-                    l.read_char();
-                } else {
-                    break;
+    fn read_digits(&mut self, radix: u32) -> &'src str {
+        let position = self.location.offset;
+        while self.ch.is_digit(radix) || self.ch == '_' {
+            self.read_char();
+        }
+        self.slice_from(position)
+    }
+
+    fn read_identifier(&mut self) -> &'src str {
+        let position = self.location.offset;
+        while !self.is_eof() && is_identifier_continue(self.ch) {
+            self.read_char();
+        }
+        // Native functions are named with a trailing `$`
+        if self.ch == '$' {
+            self.read_char();
+        }
+        self.slice_from(position)
+    }
+
+    /// Reads an integer or float literal. Integers may use a `0x`, `0o` or
+    /// `0b` prefix and end in a width suffix (`i8`, `i16`, `i32`, `i64`);
+    /// floats may have an exponent. `_` can separate digits in either.
+    fn read_number(&mut self) -> Result<tokens::Token<'src>, LexErrorKind> {
+        let position = self.location.offset;
+        let mut valid = true;
+
+        let radix = match (self.ch, self.peek_char()) {
+            ('0', 'x') => 16,
+            ('0', 'o') => 8,
+            ('0', 'b') => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.read_char();
+            self.read_char();
+        }
+
+        let val = self.read_digits(radix);
+        if !val.chars().any(|ch| ch.is_digit(radix)) {
+            valid = false;
+        }
+
+        let mut float = false;
+        if radix == 10 {
+            if self.ch == '.' && self.peek_char().is_ascii_digit() {
+                float = true;
+                self.read_char();
+                self.read_digits(10);
+            }
+
+            // A second fraction, as in `1.2.3`
+            while self.ch == '.' && self.peek_char().is_ascii_digit() {
+                valid = false;
+                self.read_char();
+                self.read_digits(10);
+            }
+
+            let sign = matches!(self.peek_char(), '+' | '-');
+            let exponent_start = self.peek_nth(1 + sign as usize).is_ascii_digit();
+            if matches!(self.ch, 'e' | 'E') && exponent_start {
+                float = true;
+                self.read_char();
+                if sign {
+                    self.read_char();
                 }
+                self.read_digits(10);
             }
+        }
 
-            l.input[position..l.position].to_vec()
+        let suffix_position = self.location.offset;
+        while self.ch.is_alphanumeric() || self.ch == '_' {
+            self.read_char();
+        }
+        let suffix = self.slice_from(suffix_position);
+        let suffix = if suffix.is_empty() {
+            None
+        } else if !float && tokens::INT_SUFFIXES.contains(&suffix) {
+            Some(suffix)
+        } else {
+            valid = false;
+            None
         };
 
+        if !valid {
+            let number = self.slice_from(position).to_string();
+            return Err(LexErrorKind::MalformedNumber(number));
+        }
+
+        if float {
+            let val = self.slice_from(position);
+            return Ok(tokens::Token::Float { val });
+        }
+
+        Ok(tokens::Token::Int { val, radix, suffix })
+    }
+
+    fn token_match(&mut self) -> Result<tokens::Token<'src>, LexErrorKind> {
         let token: tokens::Token;
-        self.skip_whitespace();
+
+        if self.is_eof() {
+            return Ok(tokens::Token::EOF);
+        }
 
         match self.ch {
             '=' => {
-                self.read_char();
-                if self.ch == '=' {
+                if self.peek_char() == '=' {
+                    self.read_char();
                     token = tokens::Token::Eq { val: ['=', '='] }
                 } else {
-                    self.read_char_back();
                     token = tokens::Token::Assign { val: self.ch };
                 }
             }
             '+' => {
-                token = tokens::Token::Plus { val: self.ch };
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    token = tokens::Token::PlusAssign { val: ['+', '='] }
+                } else if self.peek_char() == '%' {
+                    self.read_char();
+                    token = tokens::Token::WrappingPlus { val: ['+', '%'] }
+                } else {
+                    token = tokens::Token::Plus { val: self.ch };
+                }
             }
             '-' => {
-                let cur_ch = self.ch;
-                self.read_char();
-                if self.ch == '>' {
-                    token = tokens::Token::Arrow {
-                        val: [cur_ch, self.ch].into_iter().collect(),
-                    }
+                if self.peek_char() == '>' {
+                    self.read_char();
+                    token = tokens::Token::Arrow { val: "->" }
+                } else if self.peek_char() == '=' {
+                    self.read_char();
+                    token = tokens::Token::MinusAssign { val: ['-', '='] }
+                } else if self.peek_char() == '%' {
+                    self.read_char();
+                    token = tokens::Token::WrappingMinus { val: ['-', '%'] }
                 } else {
                     token = tokens::Token::Minus { val: self.ch };
                 }
             }
             '!' => {
-                self.read_char();
-                if self.ch == '=' {
+                if self.peek_char() == '=' {
+                    self.read_char();
                     token = tokens::Token::NotEq { val: ['!', '='] }
                 } else {
-                    self.read_char_back();
                     token = tokens::Token::Bang { val: self.ch };
-                }                
+                }
+            }
+            '&' if self.peek_char() == '&' => {
+                self.read_char();
+                token = tokens::Token::And;
+            }
+            '|' if self.peek_char() == '|' => {
+                self.read_char();
+                token = tokens::Token::Or;
+            }
+            '/' if self.peek_char() == '/' => {
+                self.read_char();
+                self.read_char();
+                let position = self.location.offset;
+                while !self.is_eof() && self.ch != '\n' {
+                    self.read_char();
+                }
+                let val = self.slice_from(position);
+                return Ok(tokens::Token::LineComment { val });
+            }
+            '/' if self.peek_char() == '*' => {
+                self.read_char();
+                self.read_char();
+                let position = self.location.offset;
+                let mut depth = 1;
+                loop {
+                    if self.is_eof() {
+                        return Err(LexErrorKind::UnterminatedComment);
+                    }
+                    if self.ch == '/' && self.peek_char() == '*' {
+                        depth += 1;
+                        self.read_char();
+                    } else if self.ch == '*' && self.peek_char() == '/' {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                        self.read_char();
+                    }
+                    self.read_char();
+                }
+                let val = self.slice_from(position);
+                self.read_char();
+                self.read_char();
+                return Ok(tokens::Token::BlockComment { val });
+            }
+            '/' if self.peek_char() == '=' => {
+                self.read_char();
+                token = tokens::Token::DivisionAssign { val: ['/', '='] };
             }
             '/' => {
                 token = tokens::Token::Division { val: self.ch };
             }
+            '*' if self.peek_char() == '=' => {
+                self.read_char();
+                token = tokens::Token::MultiplicationAssign { val: ['*', '='] };
+            }
+            '*' if self.peek_char() == '%' => {
+                self.read_char();
+                token = tokens::Token::WrappingMultiplication { val: ['*', '%'] };
+            }
             '*' => {
                 token = tokens::Token::Multiplication { val: self.ch };
             }
             '<' => {
-                self.read_char();
-                if self.ch == '=' {
+                if self.peek_char() == '=' {
+                    self.read_char();
                     token = tokens::Token::LtOrEq { val: ['<', '='] }
                 } else {
-                    self.read_char_back();
                     token = tokens::Token::Lt { val: self.ch };
                 }
             }
             '>' => {
-                self.read_char();
-                if self.ch == '=' {
+                if self.peek_char() == '=' {
+                    self.read_char();
                     token = tokens::Token::GtOrEq { val: ['>', '='] }
                 } else {
-                    self.read_char_back();
                     token = tokens::Token::Gt { val: self.ch };
                 }
             }
+            '.' if self.peek_char() == '.' => {
+                self.read_char();
+                token = tokens::Token::DotDot { val: ['.', '.'] };
+            }
             ';' => {
                 token = tokens::Token::Semicolon { val: self.ch };
             }
@@ -169,78 +414,448 @@ impl Lexer {
             '}' => {
                 token = tokens::Token::RBrace { val: self.ch };
             }
-            '\0' => {
-                token = tokens::Token::EOF;
-            }
-            _ if self.ch == '"' => {
-                let mut stri: Vec<char> = vec![];
+            '"' => {
                 self.read_char();
+                let position = self.location.offset;
+                // Only allocated once an escape means the text differs from the source
+                let mut unescaped: Option<String> = None;
+                let mut error = None;
                 while self.ch != '"' {
+                    if self.is_eof() {
+                        return Err(LexErrorKind::UnterminatedString);
+                    }
                     if self.ch == '\\' {
-                        self.read_char();
+                        let stri =
+                            unescaped.get_or_insert_with(|| self.slice_from(position).into());
+                        match self.read_escape() {
+                            Ok(ch) => stri.push(ch),
+                            Err(kind) => {
+                                error.get_or_insert(kind);
+                            }
+                        }
+                        continue;
+                    }
+                    if let Some(stri) = unescaped.as_mut() {
                         stri.push(self.ch);
                     }
-                    stri.push(self.ch);
                     self.read_char();
                 }
+                let val = match unescaped {
+                    Some(stri) => Cow::Owned(stri),
+                    None => Cow::Borrowed(self.slice_from(position)),
+                };
                 self.read_char();
-                return tokens::Token::String { val: stri };
+                return match error {
+                    Some(kind) => Err(kind),
+                    None => Ok(tokens::Token::String { val }),
+                };
             }
-            _ if self.ch == '\'' => {
-                self.read_char();
-                let val = self.ch.clone();
+            '\'' => {
                 self.read_char();
+                if !self.is_eof() && self.ch == '\'' {
+                    self.read_char();
+                    return Err(LexErrorKind::EmptyChar);
+                }
+                if self.is_eof() || self.ch == '\n' {
+                    return Err(LexErrorKind::UnterminatedChar);
+                }
 
-                self.read_char();
+                let val = if self.ch == '\\' {
+                    self.read_escape()
+                } else {
+                    let ch = self.ch;
+                    self.read_char();
+                    Ok(ch)
+                };
 
-                return tokens::Token::Char { val };
-            }
-            _ if self.ch.is_numeric() => {
-                let ident: Vec<char> = read_number(self);
-                if ident.contains(&'.') {
-                    return tokens::Token::Float { val: ident };
+                if self.ch != '\'' {
+                    while !self.is_eof() && self.ch != '\'' && self.ch != '\n' {
+                        self.read_char();
+                    }
+                    if self.ch == '\'' {
+                        self.read_char();
+                    }
+                    return Err(LexErrorKind::UnterminatedChar);
                 }
+                self.read_char();
 
-                return tokens::Token::Int { val: ident };
+                return val.map(|val| tokens::Token::Char { val });
+            }
+            _ if self.ch.is_whitespace() => {
+                let position = self.location.offset;
+                self.skip_whitespace();
+                return Ok(tokens::Token::Whitespace {
+                    val: self.slice_from(position),
+                });
             }
-            _ if self.ch.is_ascii_alphanumeric() => {
-                let ident: Vec<char> = read_identifier(self);
-                match Token::get_keyword_token(&ident) {
+            _ if self.ch.is_ascii_digit() => {
+                return self.read_number();
+            }
+            _ if is_identifier_start(self.ch) => {
+                let ident = self.read_identifier();
+                match Token::get_keyword_token(ident) {
                     Ok(keywork_token) => {
-                        return keywork_token;
+                        return Ok(keywork_token);
                     }
                     Err(_err) => {
-                        return tokens::Token::Identifier { val: ident };
+                        return Ok(tokens::Token::Identifier { val: ident });
                     }
                 }
             }
             _ => {
-                if self.ch == '\n' || self.ch == '\n' || self.ch == '\r' {
-                    self.line += 1;
-                    self.position = 0;
-                    // self.read_position = 0;
-                }
-
-                if self.ch == '\n' || self.ch == '\n' || self.ch == '\r' || self.ch == ' ' {
-                    return tokens::Token::Whitespace;
-                }
-                panic!(
-                    "[LEXER] Error: Unknown token found @ position {} '{}'",
-                    self.position, self.ch
-                );
-                // return tokens::Token::Unkown;
+                let ch = self.ch;
+                self.read_char();
+                return Err(LexErrorKind::UnknownCharacter(ch));
             }
         }
 
         self.read_char();
-        token
+        Ok(token)
     }
 
-    pub fn next_token(&mut self) -> tokens::Token {
-        let mut t = self.token_match();
-        if matches!(t, tokens::Token::Whitespace) {
-            t = self.token_match()
+    pub fn next_token(&mut self) -> Result<SpannedToken<'src>, LexError> {
+        loop {
+            if !self.trivia {
+                self.skip_whitespace();
+            }
+            let start = self.location;
+            let token = self.token_match();
+            let span = Span::new(start, self.location);
+            match token {
+                Ok(token) if token.is_trivia() && !self.trivia => continue,
+                Ok(token) => return Ok(SpannedToken { token, span }),
+                Err(kind) => return Err(LexError { kind, span }),
+            }
+        }
+    }
+}
+
+impl<'src> Iterator for Lexer<'src> {
+    type Item = Result<SpannedToken<'src>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_token() {
+            Ok(token) if token.token == Token::EOF => None,
+            token => Some(token),
+        }
+    }
+}
+
+impl std::iter::FusedIterator for Lexer<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokens::TokenKind;
+
+    fn lex(source: &str) -> Vec<SpannedToken<'_>> {
+        Lexer::new(source).lex().unwrap()
+    }
+
+    fn lex_errors(source: &str) -> Vec<(LexErrorKind, String)> {
+        Lexer::new(source)
+            .lex()
+            .unwrap_err()
+            .into_iter()
+            .map(|error| (error.kind, error.span.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn spans_track_offsets_lines_and_columns() {
+        let tokens = lex("let x: int = 10\nlet y = \"é\" >= 2");
+        let spans: Vec<String> = tokens.iter().map(|t| t.span.to_string()).collect();
+        assert_eq!(
+            spans,
+            [
+                "1:1-1:4",
+                "1:5-1:6",
+                "1:6-1:7",
+                "1:8-1:11",
+                "1:12-1:13",
+                "1:14-1:16",
+                "2:1-2:4",
+                "2:5-2:6",
+                "2:7-2:8",
+                "2:9-2:12",
+                "2:13-2:15",
+                "2:16-2:17",
+            ]
+        );
+
+        let ge = &tokens[10];
+        assert_eq!(ge.token, Token::GtOrEq { val: ['>', '='] });
+        assert_eq!((ge.span.start.offset, ge.span.end.offset), (29, 31));
+        assert_eq!(tokens[9].span.len(), 4);
+    }
+
+    #[test]
+    fn errors_are_collected_and_lexing_continues() {
+        let errors = lex_errors("let a = 1.2.3 # 'xy' \"ok\\q\" @\nlet b = \"open");
+        assert_eq!(
+            errors,
+            [
+                (
+                    LexErrorKind::MalformedNumber("1.2.3".into()),
+                    "1:9-1:14".into()
+                ),
+                (LexErrorKind::UnknownCharacter('#'), "1:15-1:16".into()),
+                (LexErrorKind::UnterminatedChar, "1:17-1:21".into()),
+                (LexErrorKind::InvalidEscape('q'), "1:22-1:28".into()),
+                (LexErrorKind::UnknownCharacter('@'), "1:29-1:30".into()),
+                (LexErrorKind::UnterminatedString, "2:9-2:14".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_chars_are_one_error() {
+        assert_eq!(
+            lex_errors("let c = ''\nlet d = 'x'"),
+            [(LexErrorKind::EmptyChar, "1:9-1:11".into())]
+        );
+    }
+
+    #[test]
+    fn comments_are_skipped_or_kept_as_trivia() {
+        let source = "let /* a /* nested */ note */ x: int = 4 / 2 // half\n";
+        let tokens: Vec<Token> = lex(source).into_iter().map(|t| t.token).collect();
+        assert!(tokens.contains(&Token::Division { val: '/' }));
+        assert_eq!(tokens.len(), 8);
+
+        let trivia: Vec<Token> = Lexer::with_trivia(source)
+            .lex()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.token)
+            .filter(|t| !matches!(t, Token::Whitespace { .. }) && t.is_trivia())
+            .collect();
+        assert_eq!(
+            trivia,
+            [
+                Token::BlockComment {
+                    val: " a /* nested */ note "
+                },
+                Token::LineComment { val: " half" },
+            ]
+        );
+
+        assert_eq!(
+            lex_errors("x /* open /* */"),
+            [(LexErrorKind::UnterminatedComment, "1:3-1:16".into())]
+        );
+    }
+
+    #[test]
+    fn escapes_are_decoded_in_strings_and_chars() {
+        let tokens: Vec<Token> = lex(r#""a\nb\t\"q\"\\ \u{1F600}" '\n' '\'' '\u{e9}' 'x'"#)
+            .into_iter()
+            .map(|t| t.token)
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                Token::String {
+                    val: "a\nb\t\"q\"\\ \u{1F600}".into()
+                },
+                Token::Char { val: '\n' },
+                Token::Char { val: '\'' },
+                Token::Char { val: 'é' },
+                Token::Char { val: 'x' },
+            ]
+        );
+
+        assert_eq!(
+            lex_errors(r#""\q" '\w' "\u{110000}" "\u{zz}""#),
+            [
+                (LexErrorKind::InvalidEscape('q'), "1:1-1:5".into()),
+                (LexErrorKind::InvalidEscape('w'), "1:6-1:10".into()),
+                (
+                    LexErrorKind::InvalidUnicodeEscape("110000".into()),
+                    "1:11-1:23".into()
+                ),
+                (
+                    LexErrorKind::InvalidUnicodeEscape(String::new()),
+                    "1:24-1:32".into()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn numeric_literals() {
+        let int = |val, radix, suffix| Token::Int { val, radix, suffix };
+        let float = |val| Token::Float { val };
+
+        let tokens: Vec<Token> =
+            lex("0xFF 0o17 0b1010 1_000_000 1.5e-3 2E8 10i8 0x7fi16 3i32 1.25")
+                .into_iter()
+                .map(|t| t.token)
+                .collect();
+        assert_eq!(
+            tokens,
+            [
+                int("FF", 16, None),
+                int("17", 8, None),
+                int("1010", 2, None),
+                int("1_000_000", 10, None),
+                float("1.5e-3"),
+                float("2E8"),
+                int("10", 10, Some("i8")),
+                int("7f", 16, Some("i16")),
+                int("3", 10, Some("i32")),
+                float("1.25"),
+            ]
+        );
+
+        let errors: Vec<LexErrorKind> = lex_errors("1.2.3 0b102 0x 12abc 1.5i32")
+            .into_iter()
+            .map(|(kind, _)| kind)
+            .collect();
+        assert_eq!(
+            errors,
+            ["1.2.3", "0b102", "0x", "12abc", "1.5i32"]
+                .map(|number| LexErrorKind::MalformedNumber(number.into()))
+        );
+    }
+
+    #[test]
+    fn unicode_identifiers() {
+        let ident = |val| Token::Identifier { val };
+        let tokens: Vec<Token> = lex("_ _tmp x1 größe 変数 print$ letter")
+            .into_iter()
+            .map(|t| t.token)
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                ident("_"),
+                ident("_tmp"),
+                ident("x1"),
+                ident("größe"),
+                ident("変数"),
+                ident("print$"),
+                ident("letter"),
+            ]
+        );
+
+        assert_eq!(
+            lex_errors("a$b ∑"),
+            [(LexErrorKind::UnknownCharacter('∑'), "1:5-1:6".into())]
+        );
+    }
+
+    #[test]
+    fn logical_operators() {
+        let tokens: Vec<Token> = lex("a and b && c or !d || not e")
+            .into_iter()
+            .map(|t| t.token)
+            .filter(|t| !matches!(t, Token::Identifier { .. }))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                Token::And,
+                Token::And,
+                Token::Or,
+                Token::Bang { val: '!' },
+                Token::Or,
+                Token::Not,
+            ]
+        );
+
+        assert_eq!(
+            lex_errors("a & b | c"),
+            [
+                (LexErrorKind::UnknownCharacter('&'), "1:3-1:4".into()),
+                (LexErrorKind::UnknownCharacter('|'), "1:7-1:8".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn ranges_do_not_lex_as_floats() {
+        let tokens: Vec<Token> = lex("for i in 0..n 1..2 1.5..2.5")
+            .into_iter()
+            .map(|t| t.token)
+            .collect();
+        let int = |val| Token::Int {
+            val,
+            radix: 10,
+            suffix: None,
+        };
+        let dot_dot = Token::DotDot { val: ['.', '.'] };
+        assert_eq!(
+            tokens,
+            [
+                Token::For,
+                Token::Identifier { val: "i" },
+                Token::In,
+                int("0"),
+                dot_dot.clone(),
+                Token::Identifier { val: "n" },
+                int("1"),
+                dot_dot.clone(),
+                int("2"),
+                Token::Float { val: "1.5" },
+                dot_dot,
+                Token::Float { val: "2.5" },
+            ]
+        );
+    }
+
+    #[test]
+    fn assignment_operators() {
+        let tokens: Vec<TokenKind> = lex("x = 1 x += 1 x -= 1 x *= 1 x /= 1 x - =1 a->b")
+            .into_iter()
+            .map(|t| t.token.kind())
+            .filter(|kind| !matches!(kind, TokenKind::Identifier | TokenKind::Int))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                TokenKind::Assign,
+                TokenKind::PlusAssign,
+                TokenKind::MinusAssign,
+                TokenKind::MultiplicationAssign,
+                TokenKind::DivisionAssign,
+                TokenKind::Minus,
+                TokenKind::Assign,
+                TokenKind::Arrow,
+            ]
+        );
+    }
+
+    #[test]
+    fn wrapping_operators() {
+        let tokens: Vec<TokenKind> = lex("a +% b -% c *% d + e")
+            .into_iter()
+            .map(|t| t.token.kind())
+            .filter(|kind| *kind != TokenKind::Identifier)
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                TokenKind::WrappingPlus,
+                TokenKind::WrappingMinus,
+                TokenKind::WrappingMultiplication,
+                TokenKind::Plus,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_borrow_from_the_source() {
+        let source = r#"name "plain" "esc\t""#;
+        let tokens: Vec<Token> = Lexer::new(source).map(|t| t.unwrap().token).collect();
+        match &tokens[..] {
+            [Token::Identifier { val: name }, Token::String { val: plain }, Token::String { val: escaped }] =>
+            {
+                assert!(std::ptr::eq(*name, &source[..4]));
+                assert!(matches!(plain, Cow::Borrowed("plain")));
+                assert!(matches!(escaped, Cow::Owned(escaped) if escaped == "esc\t"));
+            }
+            other => panic!("unexpected tokens {other:?}"),
         }
-        t
     }
 }
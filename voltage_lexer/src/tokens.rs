@@ -1,11 +1,26 @@
+use std::borrow::Cow;
+
+use crate::span::Span;
+
+/// Width suffixes accepted on integer literals, such as `10i8`.
+pub const INT_SUFFIXES: [&str; 4] = ["i8", "i16", "i32", "i64"];
+
 #[derive(PartialEq, Debug, Clone)]
-pub enum Token {
-    Identifier { val: Vec<char> },
+pub enum Token<'src> {
+    Identifier { val: &'src str },
     Plus { val: char },
     Minus { val: char },
     Multiplication { val: char },
     Division { val: char },
     Assign { val: char },
+    PlusAssign { val: [char; 2] },
+    MinusAssign { val: [char; 2] },
+    MultiplicationAssign { val: [char; 2] },
+    DivisionAssign { val: [char; 2] },
+    // Arithmetic that wraps around on overflow
+    WrappingPlus { val: [char; 2] },
+    WrappingMinus { val: [char; 2] },
+    WrappingMultiplication { val: [char; 2] },
     Bang { val: char },
     Semicolon { val: char },
     Colon { val: char },
@@ -14,6 +29,7 @@ pub enum Token {
     Comma { val: char },
     LBrace { val: char },
     RBrace { val: char },
+    DotDot { val: [char; 2] },
 
     // Cmp operatores
     Lt { val: char },
@@ -24,11 +40,17 @@ pub enum Token {
     NotEq { val: [char; 2] },
 
     // Types
-    Int { val: Vec<char> },
-    Float { val: Vec<char> },
-    String { val: Vec<char> },
-    Arrow { val: String },
+    Int { val: &'src str, radix: u32, suffix: Option<&'src str> },
+    Float { val: &'src str },
+    String { val: Cow<'src, str> },
+    Arrow { val: &'src str },
     Char { val: char },
+
+    // Trivia, only emitted by `Lexer::with_trivia`
+    Whitespace { val: &'src str },
+    LineComment { val: &'src str },
+    BlockComment { val: &'src str },
+
     // KEYWORDS
     Function,
     Let,
@@ -39,16 +61,95 @@ pub enum Token {
     Return,
     End,
     Module,
-    Unkown,
     Public,
+    /// `and` or `&&`
+    And,
+    /// `or` or `||`
+    Or,
+    Not,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
+    Mut,
+    Const,
+    As,
+    EOF,
+}
+
+/// The kind of a `Token` without the text it carries.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, serde::Serialize)]
+pub enum TokenKind {
+    Identifier,
+    Plus,
+    Minus,
+    Multiplication,
+    Division,
+    Assign,
+    PlusAssign,
+    MinusAssign,
+    MultiplicationAssign,
+    DivisionAssign,
+    WrappingPlus,
+    WrappingMinus,
+    WrappingMultiplication,
+    Bang,
+    Semicolon,
+    Colon,
+    LParen,
+    RParen,
+    Comma,
+    LBrace,
+    RBrace,
+    DotDot,
+    Lt,
+    Gt,
+    LtOrEq,
+    GtOrEq,
+    Eq,
+    NotEq,
+    Int,
+    Float,
+    String,
+    Arrow,
+    Char,
     Whitespace,
+    LineComment,
+    BlockComment,
+    Function,
+    Let,
+    True,
+    False,
+    If,
+    Else,
+    Return,
+    End,
+    Module,
+    Public,
+    And,
+    Or,
+    Not,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
+    Mut,
+    Const,
+    As,
     EOF,
 }
 
-impl Token {
-    pub fn get_keyword_token(ident: &Vec<char>) -> Result<Token, String> {
-        let identifier: String = ident.into_iter().collect();
-        match &identifier[..] {
+impl std::fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+impl<'src> Token<'src> {
+    pub fn get_keyword_token(ident: &str) -> Result<Token<'src>, String> {
+        match ident {
             "func" => Ok(Token::Function),
             "let" => Ok(Token::Let),
             "true" => Ok(Token::True),
@@ -59,7 +160,104 @@ impl Token {
             "return" => Ok(Token::Return),
             "module" => Ok(Token::Module),
             "public" => Ok(Token::Public),
+            "and" => Ok(Token::And),
+            "or" => Ok(Token::Or),
+            "not" => Ok(Token::Not),
+            "while" => Ok(Token::While),
+            "for" => Ok(Token::For),
+            "in" => Ok(Token::In),
+            "break" => Ok(Token::Break),
+            "continue" => Ok(Token::Continue),
+            "mut" => Ok(Token::Mut),
+            "const" => Ok(Token::Const),
+            "as" => Ok(Token::As),
             _ => Err(String::from("Not a keyword")),
         }
     }
+
+    /// Whether the token carries no meaning for the parser.
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self,
+            Token::Whitespace { .. } | Token::LineComment { .. } | Token::BlockComment { .. }
+        )
+    }
+
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Token::Identifier { .. } => TokenKind::Identifier,
+            Token::Plus { .. } => TokenKind::Plus,
+            Token::Minus { .. } => TokenKind::Minus,
+            Token::Multiplication { .. } => TokenKind::Multiplication,
+            Token::Division { .. } => TokenKind::Division,
+            Token::Assign { .. } => TokenKind::Assign,
+            Token::PlusAssign { .. } => TokenKind::PlusAssign,
+            Token::MinusAssign { .. } => TokenKind::MinusAssign,
+            Token::MultiplicationAssign { .. } => TokenKind::MultiplicationAssign,
+            Token::DivisionAssign { .. } => TokenKind::DivisionAssign,
+            Token::WrappingPlus { .. } => TokenKind::WrappingPlus,
+            Token::WrappingMinus { .. } => TokenKind::WrappingMinus,
+            Token::WrappingMultiplication { .. } => TokenKind::WrappingMultiplication,
+            Token::Bang { .. } => TokenKind::Bang,
+            Token::Semicolon { .. } => TokenKind::Semicolon,
+            Token::Colon { .. } => TokenKind::Colon,
+            Token::LParen { .. } => TokenKind::LParen,
+            Token::RParen { .. } => TokenKind::RParen,
+            Token::Comma { .. } => TokenKind::Comma,
+            Token::LBrace { .. } => TokenKind::LBrace,
+            Token::RBrace { .. } => TokenKind::RBrace,
+            Token::DotDot { .. } => TokenKind::DotDot,
+            Token::Lt { .. } => TokenKind::Lt,
+            Token::Gt { .. } => TokenKind::Gt,
+            Token::LtOrEq { .. } => TokenKind::LtOrEq,
+            Token::GtOrEq { .. } => TokenKind::GtOrEq,
+            Token::Eq { .. } => TokenKind::Eq,
+            Token::NotEq { .. } => TokenKind::NotEq,
+            Token::Int { .. } => TokenKind::Int,
+            Token::Float { .. } => TokenKind::Float,
+            Token::String { .. } => TokenKind::String,
+            Token::Arrow { .. } => TokenKind::Arrow,
+            Token::Char { .. } => TokenKind::Char,
+            Token::Whitespace { .. } => TokenKind::Whitespace,
+            Token::LineComment { .. } => TokenKind::LineComment,
+            Token::BlockComment { .. } => TokenKind::BlockComment,
+            Token::Function => TokenKind::Function,
+            Token::Let => TokenKind::Let,
+            Token::True => TokenKind::True,
+            Token::False => TokenKind::False,
+            Token::If => TokenKind::If,
+            Token::Else => TokenKind::Else,
+            Token::Return => TokenKind::Return,
+            Token::End => TokenKind::End,
+            Token::Module => TokenKind::Module,
+            Token::Public => TokenKind::Public,
+            Token::And => TokenKind::And,
+            Token::Or => TokenKind::Or,
+            Token::Not => TokenKind::Not,
+            Token::While => TokenKind::While,
+            Token::For => TokenKind::For,
+            Token::In => TokenKind::In,
+            Token::Break => TokenKind::Break,
+            Token::Continue => TokenKind::Continue,
+            Token::Mut => TokenKind::Mut,
+            Token::Const => TokenKind::Const,
+            Token::As => TokenKind::As,
+            Token::EOF => TokenKind::EOF,
+        }
+    }
+}
+
+/// A token together with the region of source it was read from.
+#[derive(PartialEq, Debug, Clone)]
+pub struct SpannedToken<'src> {
+    pub token: Token<'src>,
+    pub span: Span,
+}
+
+impl<'src> SpannedToken<'src> {
+    /// The exact source text of the token, given the source it was lexed
+    /// from.
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.span.start.offset..self.span.end.offset]
+    }
 }
@@ -0,0 +1,59 @@
+//! Lexes every file in `examples/` and compares the token dump with the
+//! checked-in output in `tests/golden`. Run with `VOLTAGE_BLESS=1` to
+//! regenerate the expected files after an intended change.
+
+use std::{env, fs, path::Path};
+
+use voltage_lexer::{dump, Lexer};
+
+#[test]
+fn examples_match_golden_token_dumps() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let bless = env::var_os("VOLTAGE_BLESS").is_some();
+
+    let mut examples: Vec<_> = fs::read_dir(root.join("../examples"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "volt"))
+        .collect();
+    examples.sort();
+    assert!(!examples.is_empty());
+
+    for example in examples {
+        let source = fs::read_to_string(&example).unwrap();
+        let tokens = Lexer::with_trivia(&source).lex().unwrap();
+
+        let round_trip: String = tokens.iter().map(|token| token.text(&source)).collect();
+        assert_eq!(
+            round_trip,
+            source,
+            "{} does not round-trip",
+            example.display()
+        );
+
+        let actual = dump::to_text(&source, &tokens);
+        let golden = root
+            .join("tests/golden")
+            .join(example.file_stem().unwrap())
+            .with_extension("tokens");
+        if bless {
+            fs::write(&golden, &actual).unwrap();
+        } else {
+            let expected = fs::read_to_string(&golden).unwrap_or_default();
+            assert_eq!(
+                actual,
+                expected,
+                "token dump of {} changed",
+                example.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn trivia_round_trips_byte_for_byte() {
+    let source = "let  s: string = \"tab\\t\" /* a /* b */ */\r\n\t// é\n  x >= 0x1F";
+    let tokens = Lexer::with_trivia(source).lex().unwrap();
+    let round_trip: String = tokens.iter().map(|token| token.text(source)).collect();
+    assert_eq!(round_trip, source);
+}
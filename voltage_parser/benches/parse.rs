@@ -0,0 +1,45 @@
+//! Times lexing and parsing of generated programs of increasing size.
+//!
+//! Run with `cargo bench -p voltage_parser`. The time per line should stay
+//! roughly flat as the input grows.
+
+use std::time::{Duration, Instant};
+
+use voltage_lexer::Lexer;
+use voltage_parser::Parser;
+
+const SIZES: [usize; 4] = [1_000, 10_000, 50_000, 100_000];
+const RUNS: u32 = 5;
+
+/// A program of roughly `lines` lines mixing functions, calls and
+/// arithmetic.
+fn generate(lines: usize) -> String {
+    let mut source = String::new();
+    for i in 0..lines / 5 {
+        source.push_str(&format!(
+            "func f{i}(a: int, b: int): int\n    let c: int = a * {i} + b / 2\n    return c - 1\nend\nlet x{i}: int = f{i}({i}, 2)\n"
+        ));
+    }
+    source
+}
+
+fn main() {
+    println!("{:>8} {:>12} {:>12}", "lines", "total", "per line");
+    for lines in SIZES {
+        let source = generate(lines);
+
+        let mut best = Duration::MAX;
+        for _ in 0..RUNS {
+            let start = Instant::now();
+            let tokens = Lexer::new(&source).lex().unwrap();
+            let (ast, errors) = Parser::new(tokens).parse();
+            let elapsed = start.elapsed();
+
+            assert!(errors.is_empty());
+            assert_eq!(ast.len(), lines / 5 * 2);
+            best = best.min(elapsed);
+        }
+
+        println!("{lines:>8} {:>12.2?} {:>12.2?}", best, best / lines as u32);
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+
+use voltage_ast::Type;
+use voltage_lexer::{span::Span, tokens::TokenKind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    /// One of `expected` was required but `found` was read instead;
+    /// `found` is `TokenKind::EOF` at the end of input.
+    UnexpectedToken {
+        expected: Vec<TokenKind>,
+        found: TokenKind,
+    },
+    ExpectedExpression {
+        found: TokenKind,
+    },
+    IntegerOutOfRange {
+        literal: String,
+        r#type: Type,
+    },
+    /// A `break` or `continue` that is not inside a loop.
+    OutsideOfLoop {
+        keyword: TokenKind,
+    },
+    UndeclaredVariable {
+        name: String,
+    },
+    /// A variable used before it is declared in a scope it is used in.
+    UsedBeforeDeclaration {
+        name: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Span,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnexpectedToken { expected, found } => {
+                let expected: Vec<String> = expected.iter().map(ToString::to_string).collect();
+                match &expected[..] {
+                    [expected] => write!(f, "expected {expected}, found {found}"),
+                    _ => write!(f, "expected one of {}, found {found}", expected.join(", ")),
+                }
+            }
+            ParseErrorKind::ExpectedExpression { found } => {
+                write!(f, "expected an expression, found {found}")
+            }
+            ParseErrorKind::IntegerOutOfRange { literal, r#type } => {
+                write!(f, "integer literal '{literal}' is out of range for {type}")
+            }
+            ParseErrorKind::OutsideOfLoop { keyword } => {
+                write!(f, "{keyword} outside of a loop")
+            }
+            ParseErrorKind::UndeclaredVariable { name } => {
+                write!(f, "no variable '{name}' in scope")
+            }
+            ParseErrorKind::UsedBeforeDeclaration { name } => {
+                write!(f, "'{name}' is used before its declaration")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[PARSER] Error @ {}: {}", self.span, self.kind)
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -1,348 +1,699 @@
 use voltage_ast::{
-    expressions::Expression, statements::Statement, CmpOperators, FuncParam, Operator, Type,
+    expressions::Expression, statements::Statement, FuncParam, Mutability, Operator, Type, TypeName,
+};
+use voltage_lexer::{
+    span::Span,
+    tokens::{SpannedToken, Token, TokenKind},
 };
-use voltage_lexer::tokens::Token;
 
-pub struct Parser {
-    tokens: Vec<Token>,
+use error::{ParseError, ParseErrorKind};
+pub use resolver::{resolve, Resolved};
+
+pub mod error;
+mod resolver;
+
+pub struct Parser<'src> {
+    tokens: Vec<SpannedToken<'src>>,
+    /// Index of the next token to be consumed.
+    pos: usize,
+    errors: Vec<ParseError>,
+    /// How many blocks deep the parser currently is.
+    depth: usize,
+    /// How many loops deep the parser currently is, within the innermost
+    /// function.
+    loop_depth: usize,
 }
 
-impl Parser {
-    pub fn new(input_token: Vec<Token>) -> Self {
+impl<'src> Parser<'src> {
+    pub fn new(input_token: Vec<SpannedToken<'src>>) -> Self {
         Self {
             tokens: input_token,
+            pos: 0,
+            errors: vec![],
+            depth: 0,
+            loop_depth: 0,
         }
     }
 
-    pub fn parse(&mut self) -> Vec<Statement> {
+    /// Parses every statement in the input. A syntax error does not stop
+    /// parsing: the parser skips ahead to the next statement boundary and
+    /// carries on, so the returned errors cover the whole file.
+    pub fn parse(&mut self) -> (Vec<Statement>, Vec<ParseError>) {
         let mut ast = vec![];
 
-        while let Some(statement) = self.parse_statement() {
-            ast.push(statement)
+        while self.peek(0).is_some() {
+            let start = self.pos;
+            match self.parse_statement() {
+                Ok(statement) => ast.push(statement),
+                Err(error) => {
+                    self.errors.push(error);
+                    // Always make progress, even when the error was on
+                    // the first token of the statement
+                    if self.pos == start {
+                        self.bump();
+                    }
+                    self.synchronize();
+                }
+            }
         }
 
-        ast
+        (ast, std::mem::take(&mut self.errors))
     }
 
-    pub fn parse_statement(&mut self) -> Option<Statement> {
-        match self.next_token() {
-            Some(Token::Let) => {
-                let identifier = match self.next_token() {
-                    Some(id) => match id {
-                        Token::Identifier { val } => String::from_iter(val),
-                        _ => panic!("Expected identifier"),
-                    },
-                    None => panic!(),
-                };
-
-                if !matches!(self.peak_next_token(), Some(Token::Colon { .. })) {
-                    panic!("Expected ':' for type declartion");
+    /// Skips tokens until the start of the next statement: just before
+    /// `let`, `const`, `func`, `if`, `while`, `for` or `return`, or just
+    /// after `;`. Inside a block
+    /// it also stops before the `end` or `}` that may close it, while at the
+    /// top level those are skipped.
+    fn synchronize(&mut self) {
+        while let Some(token) = self.peek(0) {
+            match token.token {
+                Token::Let
+                | Token::Const
+                | Token::Function
+                | Token::If
+                | Token::While
+                | Token::For
+                | Token::Return => return,
+                Token::End | Token::RBrace { .. } if self.depth > 0 => return,
+                Token::Semicolon { .. } | Token::End | Token::RBrace { .. } => {
+                    self.bump();
+                    return;
                 }
+                _ => {
+                    self.bump();
+                }
+            }
+        }
+    }
 
-                self.next_token();
-
-                let raw_type = match self.next_token() {
-                    Some(id) => match id {
-                        Token::Identifier { val } => String::from_iter(val),
-                        _ => panic!("Expected identifier"),
-                    },
-                    None => panic!(),
+    pub fn parse_statement(&mut self) -> Result<Statement, ParseError> {
+        let start = self.found().1;
+        let statement = match self.peek(0).map(|token| token.token.kind()) {
+            Some(TokenKind::Let | TokenKind::Const) => {
+                let keyword = self.bump().unwrap();
+                let mutability = if keyword.token == Token::Const {
+                    Mutability::Const
+                } else if self.at(TokenKind::Mut) {
+                    self.bump();
+                    Mutability::Mutable
+                } else {
+                    Mutability::Immutable
                 };
 
-                let r#type = Type::from(raw_type.as_str());
-
-                if !matches!(self.peak_next_token(), Some(Token::Assign { .. })) {
-                    panic!("Expected '=' for assignment operator");
-                }
-
-                self.next_token();
+                let identifier = self.expect_identifier()?;
+
+                // The type may be left out, to be inferred from the value
+                let r#type = match self
+                    .expect_one_of(&[TokenKind::Colon, TokenKind::Assign])?
+                    .token
+                {
+                    Token::Colon { .. } => {
+                        let r#type = self.parse_type()?;
+                        self.expect(TokenKind::Assign)?;
+                        Some(r#type)
+                    }
+                    _ => None,
+                };
 
-                let value = self.parse_expression(0).unwrap();
+                let value = self.parse_expression(0)?;
 
-                return Some(Statement::VariableDeclaration {
+                Statement::VariableDeclaration {
                     name: identifier,
                     value,
                     r#type,
-                });
+                    mutability,
+                    span: self.span_from(start),
+                }
             }
-            Some(Token::Function) => {
-                let mut params: Vec<FuncParam> = vec![];
-
-                let identifier = match self.next_token() {
-                    Some(id) => match id {
-                        Token::Identifier { val } => String::from_iter(val),
-                        _ => panic!("Expected identifier"),
-                    },
-                    None => panic!(),
-                };
-
-                if !matches!(self.peak_next_token(), Some(Token::LParen { .. })) {
-                    panic!("")
+            // A `func` without a name is an anonymous function expression
+            Some(TokenKind::Function)
+                if !self
+                    .peek(1)
+                    .is_some_and(|token| matches!(token.token, Token::LParen { .. })) =>
+            {
+                self.bump();
+                let identifier = self.expect_identifier()?;
+                let (params, return_type, block) = self.parse_function()?;
+
+                Statement::FunctionDeclaration {
+                    name: identifier,
+                    params,
+                    body: block,
+                    return_type,
+                    span: self.span_from(start),
                 }
+            }
+            Some(TokenKind::If) => {
+                self.bump();
+                let condition = self.parse_expression(0)?;
 
-                self.next_token();
-
-                while !matches!(self.peak_next_token(), Some(Token::RParen { .. })) {
-                    let curr_token = self.next_token();
+                self.expect(TokenKind::LBrace)?;
 
-                    let comma = matches!(curr_token, Some(Token::Comma { .. }));
+                let body = self.parse_block(TokenKind::RBrace)?;
 
-                    let identifier = if comma {
-                        match self.next_token() {
-                            Some(id) => match id {
-                                Token::Identifier { val } => String::from_iter(val),
-                                x => panic!("Expected identifier found {:?}", x),
-                            },
-                            None => panic!(),
-                        }
+                let else_body = if self.at(TokenKind::Else) {
+                    self.bump();
+                    if self.at(TokenKind::If) {
+                        Some(vec![self.parse_statement()?])
                     } else {
-                        match curr_token {
-                            Some(id) => match id {
-                                Token::Identifier { val } => String::from_iter(val),
-                                x => panic!("Expected identifier found {:?}", x),
-                            },
-                            None => panic!(),
-                        }
-                    };
-
-                    if !matches!(self.peak_next_token(), Some(Token::Colon { .. })) {
-                        panic!("Expected ':' for type declartion");
+                        self.expect(TokenKind::LBrace)?;
+                        Some(self.parse_block(TokenKind::RBrace)?)
                     }
+                } else {
+                    None
+                };
 
-                    self.next_token();
+                Statement::IfStatement {
+                    condition,
+                    body,
+                    else_body,
+                    span: self.span_from(start),
+                }
+            }
+            Some(TokenKind::While) => {
+                self.bump();
+                let condition = self.parse_expression(0)?;
 
-                    let raw_type = match self.next_token() {
-                        Some(id) => match id {
-                            Token::Identifier { val } => String::from_iter(val),
-                            _ => panic!("Expected identifier"),
-                        },
-                        None => panic!(),
-                    };
+                self.expect(TokenKind::LBrace)?;
 
-                    let r#type = Type::from(raw_type.as_str());
+                let body = self.parse_loop_body()?;
 
-                    params.push(FuncParam {
-                        name: identifier,
-                        r#type,
-                    })
+                Statement::While {
+                    condition,
+                    body,
+                    span: self.span_from(start),
                 }
+            }
+            Some(TokenKind::For) => {
+                self.bump();
+                let variable = self.expect_identifier()?;
 
-                self.next_token();
-
-                let raw_type = if !matches!(self.peak_next_token(), Some(Token::Colon { .. })) {
-                    String::from("void")
-                } else {
-                    self.next_token();
-                    match self.next_token() {
-                        Some(id) => match id {
-                            Token::Identifier { val } => String::from_iter(val),
-                            _ => panic!("Expected identifier"),
-                        },
-                        None => panic!(),
-                    }
-                };
-
-                let block = self.parse_block(Token::End);
+                self.expect(TokenKind::In)?;
 
-                let r#type = raw_type.as_str();
+                let range_start = self.parse_expression(0)?;
 
-                return Some(Statement::FunctionDeclaration {
-                    name: identifier,
-                    params,
-                    body: block,
-                    return_type: Type::from(r#type),
-                });
-            }
-            Some(Token::If) => {
-                let expr1 = self.parse_expression(0).unwrap();
-                let cmp_op = self.parse_cmp_op();
-                let expr2 = self.parse_expression(0).unwrap();
+                self.expect(TokenKind::DotDot)?;
 
-                if !matches!(self.peak_next_token(), Some(Token::LBrace { .. })) {
-                    panic!("");
-                }
+                let end = self.parse_expression(0)?;
 
-                self.next_token();
+                self.expect(TokenKind::LBrace)?;
 
-                let body = self.parse_block(Token::RBrace { val: '}' });
+                let body = self.parse_loop_body()?;
 
-                return Some(Statement::IfStatement {
-                    expr1,
-                    cmp_op,
-                    expr2,
+                Statement::For {
+                    variable,
+                    start: range_start,
+                    end,
                     body,
-                });
+                    span: self.span_from(start),
+                }
             }
-            Some(Token::Return) => {
-                let ret = self.parse_expression(0).unwrap();
-                return Some(Statement::Return { value: ret });
+            Some(TokenKind::Break | TokenKind::Continue) => {
+                let token = self.bump().unwrap();
+                if self.loop_depth == 0 {
+                    return Err(ParseError {
+                        kind: ParseErrorKind::OutsideOfLoop {
+                            keyword: token.token.kind(),
+                        },
+                        span: token.span,
+                    });
+                }
+                match token.token {
+                    Token::Break => Statement::Break { span: token.span },
+                    _ => Statement::Continue { span: token.span },
+                }
             }
-            _ => {
-                let expr = match self.parse_expression(0) {
-                    Some(expr) => expr,
-                    None => return None,
+            Some(TokenKind::Return) => {
+                self.bump();
+                // A bare `return` is followed by the end of its block
+                let bare = match self.peek(0) {
+                    None => true,
+                    Some(token) => matches!(
+                        token.token.kind(),
+                        TokenKind::End | TokenKind::RBrace | TokenKind::Semicolon | TokenKind::EOF
+                    ),
+                };
+                let value = if bare {
+                    None
+                } else {
+                    Some(self.parse_expression(0)?)
                 };
-                return Some(Statement::ExprStatement { expr });
+                Statement::Return {
+                    value,
+                    span: self.span_from(start),
+                }
+            }
+            Some(TokenKind::Identifier)
+                if self
+                    .peek(1)
+                    .is_some_and(|token| assignment_operator(&token.token).is_some()) =>
+            {
+                let name = self.expect_identifier()?;
+                let op = assignment_operator(&self.bump().unwrap().token).unwrap();
+                let value = self.parse_expression(0)?;
+                let value = match op {
+                    Some(op) => Expression::BinaryExpr {
+                        op,
+                        lhs: Box::new(Expression::Identifier {
+                            val: name.clone(),
+                            slot: None,
+                            span: start,
+                        }),
+                        span: start.to(value.span()),
+                        rhs: Box::new(value),
+                    },
+                    None => value,
+                };
+                Statement::Assignment {
+                    name,
+                    slot: None,
+                    value,
+                    span: self.span_from(start),
+                }
+            }
+            _ => {
+                let expr = self.parse_expression(0)?;
+                Statement::ExprStatement {
+                    span: expr.span(),
+                    expr,
+                }
             }
         };
-    }
 
-    pub fn parse_cmp_op(&mut self) -> voltage_ast::CmpOperators {
-        match self.next_token().unwrap() {
-            Token::Lt { .. } => CmpOperators::LessThen,
-            Token::LtOrEq { .. } => CmpOperators::LessThenOrEqual,
-            Token::Gt { .. } => CmpOperators::GreaterThen,
-            Token::GtOrEq { .. } => CmpOperators::GreaterThenOrEqual,
-            Token::Eq { .. } => CmpOperators::Equal,
-            Token::NotEq { .. } => CmpOperators::NotEqual,
-            _ => panic!("Invalid comperasion operator"),
+        // Statements may optionally be terminated with a `;`
+        if self.at(TokenKind::Semicolon) {
+            self.bump();
         }
+
+        Ok(statement)
     }
 
-    pub fn parse_block(&mut self, delimiter: Token) -> Vec<Statement> {
-        let mut block = vec![];
+    /// Parses statements up to and including `delimiter`. Errors inside the
+    /// block are recorded and parsing resumes at the next statement.
+    pub fn parse_block(&mut self, delimiter: TokenKind) -> Result<Vec<Statement>, ParseError> {
+        self.depth += 1;
+        let block = self.parse_block_statements(delimiter);
+        self.depth -= 1;
+        block
+    }
 
-        loop {
-            if self.peak_next_token() == Some(delimiter.clone()) {
-                self.next_token();
-                break;
-            }
+    /// Parses the parameters, return type and body of a function, from its
+    /// `(` up to and including its `end`.
+    fn parse_function(&mut self) -> Result<(Vec<FuncParam>, Type, Vec<Statement>), ParseError> {
+        let mut params: Vec<FuncParam> = vec![];
 
-            block.push(self.parse_statement().unwrap());
-        }
+        self.expect(TokenKind::LParen)?;
 
-        block
-    }
+        if !self.at(TokenKind::RParen) {
+            loop {
+                let identifier = self.expect_identifier()?;
 
-    pub fn next_token(&mut self) -> Option<Token> {
-        let mut tokens = self.tokens.clone().into_iter();
+                self.expect(TokenKind::Colon)?;
 
-        let token = tokens.next().clone();
+                let r#type = self.parse_type()?;
 
-        let tokens_vec = tokens.collect::<Vec<Token>>();
-        self.tokens = tokens_vec;
+                params.push(FuncParam {
+                    name: identifier,
+                    r#type,
+                });
 
-        token
-    }
+                if self.at(TokenKind::Comma) {
+                    self.bump();
+                } else {
+                    break;
+                }
+            }
+        }
 
-    pub fn peak_next_token(&mut self) -> Option<Token> {
-        let mut tokens = self.tokens.iter();
+        self.expect_one_of(&[TokenKind::Comma, TokenKind::RParen])?;
 
-        let token = match tokens.next().clone() {
-            Some(token) => token,
-            None => return None,
+        let return_type = if self.at(TokenKind::Colon) {
+            self.bump();
+            self.parse_type()?
+        } else {
+            Type::Void
         };
 
-        tokens.next_back();
+        // Loops do not extend into a function declared inside them
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        let block = self.parse_block(TokenKind::End);
+        self.loop_depth = loop_depth;
 
-        Some(token.clone())
+        Ok((params, return_type, block?))
     }
 
-    pub fn forward(&mut self, times: usize) -> Option<Token> {
-        let mut tokens = self.tokens.iter();
+    /// Parses the body of a loop, up to and including its `}`.
+    fn parse_loop_body(&mut self) -> Result<Vec<Statement>, ParseError> {
+        self.loop_depth += 1;
+        let body = self.parse_block(TokenKind::RBrace);
+        self.loop_depth -= 1;
+        body
+    }
 
-        let mut cur_time = 0;
+    fn parse_block_statements(
+        &mut self,
+        delimiter: TokenKind,
+    ) -> Result<Vec<Statement>, ParseError> {
+        let mut block = vec![];
 
         loop {
-            if cur_time >= times - 1 {
+            if self.at(delimiter) {
+                self.bump();
                 break;
             }
 
-            tokens.next();
+            if self.peek(0).is_none() {
+                return Err(self.unexpected(vec![delimiter]));
+            }
 
-            cur_time += 1;
+            let start = self.pos;
+            match self.parse_statement() {
+                Ok(statement) => block.push(statement),
+                Err(error) => {
+                    self.errors.push(error);
+                    if self.pos == start {
+                        self.bump();
+                    }
+                    self.synchronize();
+                }
+            }
         }
 
-        let token = match tokens.next().clone() {
-            Some(token) => token,
-            None => return None,
-        };
+        Ok(block)
+    }
 
-        let mut cur_time = 0;
+    pub fn parse_type(&mut self) -> Result<Type, ParseError> {
+        // `func(int, int): int`, the return type defaulting to `void`
+        if self.at(TokenKind::Function) {
+            self.bump();
+            self.expect(TokenKind::LParen)?;
 
-        loop {
-            if cur_time >= times {
-                break;
+            let mut params = vec![];
+            if !self.at(TokenKind::RParen) {
+                loop {
+                    params.push(self.parse_type()?);
+
+                    if self.at(TokenKind::Comma) {
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
             }
 
-            tokens.next_back();
+            self.expect_one_of(&[TokenKind::Comma, TokenKind::RParen])?;
+
+            let return_type = if self.at(TokenKind::Colon) {
+                self.bump();
+                self.parse_type()?
+            } else {
+                Type::Void
+            };
+            return Ok(Type::Function {
+                params,
+                return_type: Box::new(return_type),
+            });
+        }
 
-            cur_time += 1;
+        let start = self.found().1;
+        let raw_type = self.expect_identifier()?;
+        match Type::from(raw_type.as_str()) {
+            Type::Unknown => Ok(Type::Undefined(Box::new(TypeName {
+                name: raw_type,
+                span: self.span_from(start),
+            }))),
+            r#type => Ok(r#type),
         }
+    }
+
+    /// The token `n` places ahead of the cursor, `peek(0)` being the next
+    /// one to be consumed.
+    pub fn peek(&self, n: usize) -> Option<&SpannedToken<'src>> {
+        self.tokens.get(self.pos + n)
+    }
+
+    /// Consumes and returns the next token.
+    pub fn bump(&mut self) -> Option<SpannedToken<'src>> {
+        let token = self.tokens.get(self.pos)?.clone();
+        self.pos += 1;
+        Some(token)
+    }
 
-        Some(token.clone())
+    /// Whether the next token is of `kind`.
+    pub fn at(&self, kind: TokenKind) -> bool {
+        matches!(self.peek(0), Some(token) if token.token.kind() == kind)
     }
 
-    pub fn parse_expression(&mut self, bp: u8) -> Option<Expression> {
-        let mut lhs = match self.next_token() {
-            Some(Token::String { val }) => {
-                let val: String = val.into_iter().collect();
-                Expression::StringLiteral { val }
+    /// Consumes the next token if it is of `kind`.
+    pub fn expect(&mut self, kind: TokenKind) -> Result<SpannedToken<'src>, ParseError> {
+        self.expect_one_of(&[kind])
+    }
+
+    /// Consumes the next token if it is one of `kinds`.
+    pub fn expect_one_of(&mut self, kinds: &[TokenKind]) -> Result<SpannedToken<'src>, ParseError> {
+        match self.peek(0) {
+            Some(token) if kinds.contains(&token.token.kind()) => Ok(self.bump().unwrap()),
+            _ => Err(self.unexpected(kinds.to_vec())),
+        }
+    }
+
+    pub fn expect_identifier(&mut self) -> Result<String, ParseError> {
+        let token = self.expect(TokenKind::Identifier)?;
+        match token.token {
+            Token::Identifier { val } => Ok(val.to_string()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// An error for the next token, which was not one of `expected`.
+    fn unexpected(&self, expected: Vec<TokenKind>) -> ParseError {
+        let (found, span) = self.found();
+        ParseError {
+            kind: ParseErrorKind::UnexpectedToken { expected, found },
+            span,
+        }
+    }
+
+    /// The span from the start of `start` to the end of the last consumed
+    /// token.
+    fn span_from(&self, start: Span) -> Span {
+        match self.pos.checked_sub(1).and_then(|pos| self.tokens.get(pos)) {
+            Some(token) => start.to(token.span),
+            None => start,
+        }
+    }
+
+    /// The kind and span of the next token, or `EOF` just past the last one.
+    fn found(&self) -> (TokenKind, Span) {
+        match self.peek(0) {
+            Some(token) => (token.token.kind(), token.span),
+            None => {
+                let end = self.tokens.last().map(|token| token.span.end);
+                let end = end.unwrap_or_default();
+                (TokenKind::EOF, Span::new(end, end))
             }
-            Some(Token::Identifier { val }) => {
-                let val: String = val.into_iter().collect();
-                Expression::Identifier { val }
+        }
+    }
+
+    /// Builds an `IntLiteral` typed by its suffix, recording an error if the
+    /// value does not fit in that type. `negative` is set for a literal
+    /// directly preceded by `-`, so that e.g. `-128i8` is in range.
+    fn parse_int_literal(
+        &mut self,
+        val: &str,
+        radix: u32,
+        suffix: Option<&str>,
+        negative: bool,
+        span: Span,
+    ) -> Expression {
+        let r#type = match suffix {
+            Some(suffix) => Type::from(suffix),
+            None => Type::Int,
+        };
+
+        let digits = val.replace('_', "");
+        let parsed = u64::from_str_radix(&digits, radix).map(|val| {
+            if negative {
+                -(val as i128)
+            } else {
+                val as i128
             }
-            Some(Token::Int { val }) => {
-                let val: String = val.into_iter().collect();
+        });
+        match parsed {
+            Ok(val) if fits(&r#type, val) => Expression::IntLiteral {
+                val: val as i64,
+                r#type,
+                span,
+            },
+            _ => {
+                let sign = if negative { "-" } else { "" };
+                let prefix = match radix {
+                    16 => "0x",
+                    8 => "0o",
+                    2 => "0b",
+                    _ => "",
+                };
+                self.errors.push(ParseError {
+                    kind: ParseErrorKind::IntegerOutOfRange {
+                        literal: format!("{sign}{prefix}{val}{}", suffix.unwrap_or_default()),
+                        r#type: r#type.clone(),
+                    },
+                    span,
+                });
                 Expression::IntLiteral {
-                    val: val.parse().unwrap(),
+                    val: 0,
+                    r#type,
+                    span,
                 }
             }
-            Some(Token::Float { val }) => {
-                let val: String = val.into_iter().collect();
-                Expression::FloatLiteral {
-                    val: val.parse().unwrap(),
+        }
+    }
+
+    pub fn parse_expression(&mut self, bp: u8) -> Result<Expression, ParseError> {
+        let Some(token) = self.bump() else {
+            let (found, span) = self.found();
+            return Err(ParseError {
+                kind: ParseErrorKind::ExpectedExpression { found },
+                span,
+            });
+        };
+
+        let mut lhs = match token.token {
+            Token::String { val } => Expression::StringLiteral {
+                val: val.into_owned(),
+                span: token.span,
+            },
+            Token::Identifier { val } => Expression::Identifier {
+                val: val.to_string(),
+                slot: None,
+                span: token.span,
+            },
+            Token::Int { val, radix, suffix } => {
+                self.parse_int_literal(val, radix, suffix, false, token.span)
+            }
+            Token::Float { val } => Expression::FloatLiteral {
+                val: val.replace('_', "").parse().unwrap(),
+                span: token.span,
+            },
+            Token::Char { val } => Expression::CharLiteral {
+                val,
+                span: token.span,
+            },
+            Token::True => Expression::BooleanLiteral {
+                val: true,
+                span: token.span,
+            },
+            Token::False => Expression::BooleanLiteral {
+                val: false,
+                span: token.span,
+            },
+            Token::Minus { .. } => match self.peek(0) {
+                // Negative literals are range checked as a whole
+                Some(&SpannedToken {
+                    token: Token::Int { val, radix, suffix },
+                    span,
+                }) => {
+                    self.bump();
+                    self.parse_int_literal(val, radix, suffix, true, token.span.to(span))
+                }
+                _ => {
+                    let child = self.parse_expression(PREFIX_BINDING_POWER)?;
+                    make_prefix_expr(Operator::Minus, child, token.span)
+                }
+            },
+            Token::LParen { .. } => {
+                let expr = self.parse_expression(0)?;
+                self.expect(TokenKind::RParen)?;
+                expr
+            }
+            Token::Plus { .. } => {
+                let child = self.parse_expression(PREFIX_BINDING_POWER)?;
+                make_prefix_expr(Operator::Plus, child, token.span)
+            }
+            Token::Function => {
+                let (params, return_type, body) = self.parse_function()?;
+                Expression::Function {
+                    params,
+                    return_type,
+                    body,
+                    span: self.span_from(token.span),
                 }
             }
-            Some(Token::Char { val }) => Expression::CharLiteral { val },
-            Some(Token::True) => Expression::BooleanLiteral { val: true },
-            Some(Token::False) => Expression::BooleanLiteral { val: false },
-            x => return None,
+            Token::Bang { .. } | Token::Not => {
+                let child = self.parse_expression(PREFIX_BINDING_POWER)?;
+                make_prefix_expr(Operator::Not, child, token.span)
+            }
+            found => {
+                return Err(ParseError {
+                    kind: ParseErrorKind::ExpectedExpression {
+                        found: found.kind(),
+                    },
+                    span: token.span,
+                })
+            }
         };
 
-        loop {
-            let infix = if let Some(infix) = self.peak_next_token() {
-                if matches!(self.forward(1), Some(Token::LParen { .. })) {
-                    self.next_token();
+        while let Some(infix) = self.peek(0) {
+            // A call binds tighter than any prefix or infix operator, and
+            // may follow any expression, e.g. `get_fn()(1)`
+            if matches!(infix.token, Token::LParen { .. }) {
+                self.bump();
 
-                    let mut params: Vec<Expression> = vec![];
+                let mut params: Vec<Expression> = vec![];
 
+                if !self.at(TokenKind::RParen) {
                     loop {
-                        if self.peak_next_token() == Some(Token::RParen { val: ')' }) {
-                            self.next_token();
-                            break;
-                        }
+                        params.push(self.parse_expression(0)?);
 
-                        if self.peak_next_token() == Some(Token::Comma { val: ',' }) {
-                            self.next_token();
-                            params.push(self.parse_expression(0).unwrap());
+                        if self.at(TokenKind::Comma) {
+                            self.bump();
                         } else {
-                            params.push(self.parse_expression(0).unwrap());
+                            break;
                         }
                     }
+                }
 
-                    lhs = Expression::FunctionCall {
-                        name: Box::new(lhs),
-                        params,
-                    }
+                let close = self.expect_one_of(&[TokenKind::Comma, TokenKind::RParen])?;
+                let span = lhs.span().to(close.span);
+
+                lhs = Expression::FunctionCall {
+                    name: Box::new(lhs),
+                    params,
+                    span,
+                };
+
+                continue;
+            }
+
+            // `as` binds tighter than any infix operator, but looser than a
+            // prefix one, so `-x as i8` casts `-x`
+            if matches!(infix.token, Token::As) {
+                if CAST_BINDING_POWER < bp {
+                    break;
                 }
+                self.bump();
 
-                infix
-            } else {
-                break;
-            };
+                let r#type = self.parse_type()?;
+                lhs = Expression::Cast {
+                    span: self.span_from(lhs.span()),
+                    expr: Box::new(lhs),
+                    r#type,
+                };
 
-            if let Some((lbp, rbp)) = infix_binding_power(infix) {
+                continue;
+            }
+
+            if let Some((op, lbp, rbp)) = infix_operator(&infix.token) {
                 if lbp < bp {
                     break;
                 }
 
-                let next_op = self.next_token().unwrap().clone();
+                self.bump();
 
-                let rhs = self.parse_expression(rbp);
+                let rhs = self.parse_expression(rbp)?;
 
-                lhs = make_infix_expr(lhs.clone(), next_op, rhs.unwrap());
+                lhs = make_infix_expr(lhs, op, rhs);
 
                 continue;
             }
@@ -350,44 +701,486 @@ impl Parser {
             break;
         }
 
-        Some(lhs)
+        Ok(lhs)
     }
 }
 
-fn infix_binding_power(token: Token) -> Option<(u8, u8)> {
-    let bp = match token {
-        Token::Multiplication { .. } | Token::Division { .. } => (8, 9),
-        Token::Plus { .. } | Token::Minus { .. } => (6, 7),
+/// Whether `val` can be held by an integer of type `r#type`.
+fn fits(r#type: &Type, val: i128) -> bool {
+    r#type.int_range().is_some_and(|range| range.contains(&val))
+}
+
+/// For an assignment token, the operator it applies before assigning, if
+/// any.
+fn assignment_operator(token: &Token) -> Option<Option<Operator>> {
+    let op = match token {
+        Token::Assign { .. } => None,
+        Token::PlusAssign { .. } => Some(Operator::Plus),
+        Token::MinusAssign { .. } => Some(Operator::Minus),
+        Token::MultiplicationAssign { .. } => Some(Operator::Multiplication),
+        Token::DivisionAssign { .. } => Some(Operator::Division),
+        _ => return None,
+    };
+    Some(op)
+}
+
+/// Binding power of prefix operators, tighter than any infix operator.
+const PREFIX_BINDING_POWER: u8 = 10;
+
+/// Left binding power of `as`, between that of prefix and infix operators.
+const CAST_BINDING_POWER: u8 = 9;
+
+/// For an infix operator token, the operator it stands for with its left
+/// and right binding power.
+fn infix_operator(token: &Token) -> Option<(Operator, u8, u8)> {
+    let (op, bp) = match token {
+        Token::Multiplication { .. } => (Operator::Multiplication, (8, 9)),
+        Token::Division { .. } => (Operator::Division, (8, 9)),
+        Token::WrappingMultiplication { .. } => (Operator::WrappingMultiplication, (8, 9)),
+        Token::Plus { .. } => (Operator::Plus, (6, 7)),
+        Token::Minus { .. } => (Operator::Minus, (6, 7)),
+        Token::WrappingPlus { .. } => (Operator::WrappingPlus, (6, 7)),
+        Token::WrappingMinus { .. } => (Operator::WrappingMinus, (6, 7)),
+        Token::Lt { .. } => (Operator::LessThen, (4, 5)),
+        Token::Gt { .. } => (Operator::GreaterThen, (4, 5)),
+        Token::LtOrEq { .. } => (Operator::LessThenOrEqual, (4, 5)),
+        Token::GtOrEq { .. } => (Operator::GreaterThenOrEqual, (4, 5)),
+        Token::Eq { .. } => (Operator::Equal, (4, 5)),
+        Token::NotEq { .. } => (Operator::NotEqual, (4, 5)),
+        Token::And => (Operator::And, (3, 4)),
+        Token::Or => (Operator::Or, (1, 2)),
         _ => return None,
     };
 
-    Some(bp)
+    Some((op, bp.0, bp.1))
 }
 
-fn make_infix_expr(lhs: Expression, op: Token, rhs: Expression) -> Expression {
-    let lhs = Box::new(lhs);
-    let rhs = Box::new(rhs);
-    match op {
-        Token::Plus { .. } => Expression::BinaryExpr {
-            op: Operator::Plus,
-            lhs,
-            rhs,
-        },
-        Token::Multiplication { .. } => Expression::BinaryExpr {
-            op: Operator::Multiplication,
-            lhs,
-            rhs,
-        },
-        Token::Minus { .. } => Expression::BinaryExpr {
-            op: Operator::Minus,
-            lhs,
-            rhs,
-        },
-        Token::Division { .. } => Expression::BinaryExpr {
-            op: Operator::Division,
-            lhs,
-            rhs,
+/// Builds a prefix expression, folding it into a literal when `child` is
+/// a literal the operator applies to.
+fn make_prefix_expr(op: Operator, child: Expression, op_span: Span) -> Expression {
+    let span = op_span.to(child.span());
+    match (op, child) {
+        (Operator::Minus, Expression::IntLiteral { val, r#type, .. })
+            if fits(&r#type, -(val as i128)) =>
+        {
+            Expression::IntLiteral {
+                val: -val,
+                r#type,
+                span,
+            }
+        }
+        (Operator::Minus, Expression::FloatLiteral { val, .. }) => {
+            Expression::FloatLiteral { val: -val, span }
+        }
+        (
+            Operator::Plus,
+            child @ (Expression::IntLiteral { .. } | Expression::FloatLiteral { .. }),
+        ) => child,
+        (Operator::Not, Expression::BooleanLiteral { val, .. }) => {
+            Expression::BooleanLiteral { val: !val, span }
+        }
+        (op, child) => Expression::UnaryExpr {
+            op,
+            child: Box::new(child),
+            span,
         },
-        _ => unimplemented!(),
+    }
+}
+
+fn make_infix_expr(lhs: Expression, op: Operator, rhs: Expression) -> Expression {
+    Expression::BinaryExpr {
+        op,
+        span: lhs.span().to(rhs.span()),
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use voltage_lexer::Lexer;
+
+    fn parse(source: &str) -> (Vec<Statement>, Vec<ParseError>) {
+        let tokens = Lexer::new(source).lex().unwrap();
+        Parser::new(tokens).parse()
+    }
+
+    /// Each statement printed as an S-expression.
+    fn printed(ast: &[Statement]) -> Vec<String> {
+        ast.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn int_literals_are_typed_and_range_checked() {
+        let (ast, errors) = parse(
+            "let a: i16 = 0xFFi16 let b: int = 1_000 let c: i8 = 127i8
+             let d: i8 = 128i8 let e: int = 99999999999999999999",
+        );
+        let literals: Vec<(i64, Type)> = ast
+            .into_iter()
+            .map(|statement| match statement {
+                Statement::VariableDeclaration {
+                    value: Expression::IntLiteral { val, r#type, .. },
+                    ..
+                } => (val, r#type),
+                other => panic!("expected declaration, found {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            literals[..3],
+            [(255, Type::Int16), (1000, Type::Int), (127, Type::Int8)]
+        );
+        let errors: Vec<(ParseErrorKind, String)> = errors
+            .into_iter()
+            .map(|error| (error.kind, error.span.to_string()))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (
+                    ParseErrorKind::IntegerOutOfRange {
+                        literal: "128i8".into(),
+                        r#type: Type::Int8
+                    },
+                    "2:26-2:31".into()
+                ),
+                (
+                    ParseErrorKind::IntegerOutOfRange {
+                        literal: "99999999999999999999".into(),
+                        r#type: Type::Int
+                    },
+                    "2:45-2:65".into()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_are_collected_and_parsing_resumes_at_statement_boundaries() {
+        let (ast, errors) = parse(
+            "let x int = 1;
+             func f(a: int, b: int): int
+                 let y: int = * 2
+                 return a
+             end
+             if x 1 { }
+             let z: int = add(1, 2",
+        );
+
+        assert_eq!(
+            printed(&ast),
+            ["(func f ((a int) (b int)) int ((return a)))"]
+        );
+
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            [
+                "[PARSER] Error @ 1:7-1:10: expected one of Colon, Assign, found Identifier",
+                "[PARSER] Error @ 3:31-3:32: expected an expression, found Multiplication",
+                "[PARSER] Error @ 6:19-6:20: expected LBrace, found Int",
+                "[PARSER] Error @ 7:35-7:35: expected one of Comma, RParen, found EOF",
+            ]
+        );
+    }
+
+    #[test]
+    fn else_if_chains_nest_in_the_else_body() {
+        let (ast, errors) = parse(
+            "if x < 0 { return 0 } else if x == 0 { return 1 } else { return 2 }
+             if x > 0 { }",
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            printed(&ast),
+            [
+                "(if (< x 0) ((return 0)) ((if (== x 0) ((return 1)) ((return 2)))))",
+                "(if (> x 0) ())",
+            ]
+        );
+    }
+
+    #[test]
+    fn comparisons_bind_looser_than_arithmetic() {
+        let (ast, errors) = parse("let b: bool = a + 1 < c * 2 if b { }");
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            printed(&ast),
+            ["(let b bool (< (+ a 1) (* c 2)))", "(if b ())"]
+        );
+    }
+
+    #[test]
+    fn logical_operators_bind_looser_than_comparisons() {
+        let (ast, errors) = parse("a < b and not c or !d == e");
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(printed(&ast), ["(or (and (< a b) (not c)) (== (not d) e))"]);
+    }
+
+    #[test]
+    fn prefix_operators_fold_literals() {
+        let (ast, errors) = parse("-5; -x * 2; +1.5; !true; -128i8; -129i8; a - -b; --3");
+        assert_eq!(
+            printed(&ast),
+            [
+                "-5",
+                "(* (- x) 2)",
+                "1.5",
+                "false",
+                "-128",
+                "0",
+                "(- a (- b))",
+                "3"
+            ]
+        );
+        assert!(matches!(
+            ast[4],
+            Statement::ExprStatement {
+                expr: Expression::IntLiteral {
+                    r#type: Type::Int8,
+                    ..
+                },
+                ..
+            }
+        ));
+
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            ["[PARSER] Error @ 1:34-1:40: integer literal '-129i8' is out of range for i8"]
+        );
+    }
+
+    #[test]
+    fn loops_and_loop_control() {
+        let (ast, errors) = parse(
+            "while x < 10 { if x == 5 { break } continue }
+             for i in 0..n + 1 { }
+             break
+             for i in 0..3 {
+                 func f()
+                     continue
+                 end
+             }",
+        );
+
+        assert_eq!(
+            printed(&ast[..2]),
+            [
+                "(while (< x 10) ((if (== x 5) ((break))) (continue)))",
+                "(for i 0 (+ n 1) ())",
+            ]
+        );
+
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            [
+                "[PARSER] Error @ 3:14-3:19: Break outside of a loop",
+                "[PARSER] Error @ 6:22-6:30: Continue outside of a loop",
+            ]
+        );
+    }
+
+    #[test]
+    fn declarations_and_assignments() {
+        let (ast, errors) = parse(
+            "let mut x: int = 1 let y: int = 2 const z: int = 3 let w = 4
+             x = y x += y * 2 x /= 2 z(1) = 4",
+        );
+
+        assert_eq!(
+            printed(&ast),
+            [
+                "(let mut x int 1)",
+                "(let y int 2)",
+                "(const z int 3)",
+                "(let w _ 4)",
+                "(= x y)",
+                "(= x (+ x (* y 2)))",
+                "(= x (/ x 2))",
+                "(call z 1)",
+            ]
+        );
+
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            ["[PARSER] Error @ 2:43-2:44: expected an expression, found Assign"]
+        );
+    }
+
+    #[test]
+    fn function_types_and_anonymous_functions() {
+        let (ast, errors) = parse(
+            "func apply(f: func(int, int): int, g: func()): func(): bool
+                 g()
+             end
+             let double = func(x: int): int return x * 2 end
+             func() end()
+             apply(func(a: int, b: int): int
+                 return a + b
+             end, func() end)",
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            printed(&ast),
+            [
+                "(func apply ((f func(int, int): int) (g func(): void)) func(): bool ((call g)))",
+                "(let double _ (func ((x int)) int ((return (* x 2)))))",
+                "(call (func () void ()))",
+                "(call apply (func ((a int) (b int)) int ((return (+ a b)))) (func () void ()))",
+            ]
+        );
+    }
+
+    #[test]
+    fn nodes_span_their_source() {
+        let source = "func add(a: int, b: int): int
+    return a + -b
+end
+let mut total = add(1, 2) * 3;
+total += 1";
+        let (ast, errors) = parse(source);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let text = |span: Span| &source[span.start.offset..span.end.offset];
+        let statements: Vec<&str> = ast.iter().map(|statement| text(statement.span())).collect();
+        assert_eq!(
+            statements,
+            [
+                "func add(a: int, b: int): int\n    return a + -b\nend",
+                "let mut total = add(1, 2) * 3",
+                "total += 1",
+            ]
+        );
+
+        let Statement::FunctionDeclaration { body, .. } = &ast[0] else {
+            panic!("expected a function, found {:?}", ast[0]);
+        };
+        let Statement::Return {
+            value: Some(value), ..
+        } = &body[0]
+        else {
+            panic!("expected a return, found {:?}", body[0]);
+        };
+        assert_eq!(text(value.span()), "a + -b");
+        let Expression::BinaryExpr { rhs, .. } = value else {
+            panic!("expected a binary expression, found {value:?}");
+        };
+        assert_eq!(text(rhs.span()), "-b");
+
+        let Statement::VariableDeclaration {
+            value: Expression::BinaryExpr { lhs, .. },
+            ..
+        } = &ast[1]
+        else {
+            panic!("expected a declaration, found {:?}", ast[1]);
+        };
+        assert_eq!(text(lhs.span()), "add(1, 2)");
+    }
+
+    #[test]
+    fn bare_returns() {
+        let (ast, errors) = parse(
+            "func f()
+    if true { return }
+    while true { return; }
+    return
+end
+func g(): int
+    return -1
+end",
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            printed(&ast),
+            [
+                "(func f () void ((if true ((return))) (while true ((return))) (return)))",
+                "(func g () int ((return -1)))",
+            ]
+        );
+    }
+
+    #[test]
+    fn variables_resolve_to_slots() {
+        let source = "let x = 1
+func f(a: int): int
+    if a > x {
+        return a
+    }
+    return f(a - 1)
+end
+x = 2";
+        let (ast, errors) = parse(source);
+        assert!(errors.is_empty(), "{errors:?}");
+        let ast = resolve(ast).unwrap();
+
+        let slot = |expr: &Expression| match expr {
+            Expression::Identifier { slot, .. } => slot.map(|slot| (slot.depth, slot.index)),
+            other => panic!("expected an identifier, found {other:?}"),
+        };
+        let Statement::FunctionDeclaration { body, .. } = &ast[1] else {
+            panic!("expected a function, found {:?}", ast[1]);
+        };
+        let Statement::IfStatement {
+            condition: Expression::BinaryExpr { lhs, rhs, .. },
+            body: if_body,
+            ..
+        } = &body[0]
+        else {
+            panic!("expected an if, found {:?}", body[0]);
+        };
+        assert_eq!(slot(lhs), Some((0, 0)));
+        assert_eq!(slot(rhs), Some((1, 1)));
+        let Statement::Return {
+            value: Some(value), ..
+        } = &if_body[0]
+        else {
+            panic!("expected a return, found {:?}", if_body[0]);
+        };
+        assert_eq!(slot(value), Some((1, 0)));
+        let Statement::Return {
+            value: Some(Expression::FunctionCall { name, .. }),
+            ..
+        } = &body[1]
+        else {
+            panic!("expected a call, found {:?}", body[1]);
+        };
+        // Functions take the first slots of their block
+        assert_eq!(slot(name), Some((1, 0)));
+
+        let Statement::Assignment { slot, .. } = &ast[2] else {
+            panic!("expected an assignment, found {:?}", ast[2]);
+        };
+        assert_eq!(slot.map(|slot| (slot.depth, slot.index)), Some((0, 1)));
+    }
+
+    #[test]
+    fn variables_used_before_their_declaration() {
+        let source = "func f(): int
+    return later
+end
+let later = 1
+let z = z
+if true {
+    sibling
+}
+if true {
+    let sibling = 2
+}";
+        let (ast, errors) = parse(source);
+        assert!(errors.is_empty(), "{errors:?}");
+        let errors = resolve(ast).unwrap_err();
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            [
+                "[PARSER] Error @ 2:12-2:17: 'later' is used before its declaration",
+                "[PARSER] Error @ 5:9-5:10: 'z' is used before its declaration",
+                "[PARSER] Error @ 7:5-7:12: no variable 'sibling' in scope",
+            ]
+        );
     }
 }
@@ -0,0 +1,216 @@
+use std::ops::Deref;
+
+use voltage_ast::{expressions::Expression, statements::Statement, FuncParam, Slot};
+use voltage_lexer::span::Span;
+
+use crate::error::{ParseError, ParseErrorKind};
+
+/// Resolves the slot of every variable used in `ast`, following the scopes
+/// the engine runs it in: one for the whole file, one for each call of a
+/// function holding its parameters and body, and one for each run of an
+/// `if`, `while` or `for` body. The functions declared in a block take its
+/// first slots, after any parameters.
+pub fn resolve(mut ast: Vec<Statement>) -> Result<Resolved, Vec<ParseError>> {
+    let mut resolver = Resolver {
+        scopes: vec![vec![]],
+        pending: vec![],
+        errors: vec![],
+    };
+    resolver.resolve_block(&mut ast);
+    for (name, span, _) in std::mem::take(&mut resolver.pending) {
+        resolver.error(ParseErrorKind::UndeclaredVariable { name }, span);
+    }
+    if !resolver.errors.is_empty() {
+        resolver.errors.sort_by_key(|error| error.span);
+        return Err(resolver.errors);
+    }
+    Ok(Resolved { ast })
+}
+
+/// A program with every variable resolved to its slot. Only [`resolve`]
+/// makes one, so whatever runs it can rely on the slots being there.
+#[derive(Debug)]
+pub struct Resolved {
+    ast: Vec<Statement>,
+}
+
+impl Resolved {
+    pub fn into_statements(self) -> Vec<Statement> {
+        self.ast
+    }
+}
+
+impl Deref for Resolved {
+    type Target = [Statement];
+
+    fn deref(&self) -> &[Statement] {
+        &self.ast
+    }
+}
+
+struct Resolver {
+    /// The names declared in each open scope, innermost last.
+    scopes: Vec<Vec<String>>,
+    /// Names used where nothing declared them yet, with the innermost open
+    /// scope they were used in, in case a later declaration comes into
+    /// scope there.
+    pending: Vec<(String, Span, usize)>,
+    errors: Vec<ParseError>,
+}
+
+impl Resolver {
+    fn error(&mut self, kind: ParseErrorKind, span: Span) {
+        self.errors.push(ParseError { kind, span });
+    }
+
+    fn resolve_block(&mut self, body: &mut [Statement]) {
+        // Functions are declared before the block runs, so that they can
+        // call each other whatever order they are written in
+        for statement in body.iter() {
+            if let Statement::FunctionDeclaration { name, .. } = statement {
+                self.declare(name.clone());
+            }
+        }
+        for statement in body {
+            self.resolve_statement(statement);
+        }
+    }
+
+    /// Resolves `body` in a scope of its own, starting with `variables`.
+    fn resolve_scoped(&mut self, variables: Vec<String>, body: &mut [Statement]) {
+        self.scopes.push(vec![]);
+        for name in variables {
+            self.declare(name);
+        }
+        self.resolve_block(body);
+        self.scopes.pop();
+
+        let innermost = self.scopes.len() - 1;
+        for (_, _, scope) in &mut self.pending {
+            *scope = (*scope).min(innermost);
+        }
+    }
+
+    fn resolve_function(&mut self, params: &[FuncParam], body: &mut [Statement]) {
+        let params = params.iter().map(|param| param.name.clone()).collect();
+        self.resolve_scoped(params, body);
+    }
+
+    fn declare(&mut self, name: String) {
+        let innermost = self.scopes.len() - 1;
+        let (used, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|(used, _, scope)| *used == name && *scope >= innermost);
+        self.pending = pending;
+        for (name, span, _) in used {
+            self.error(ParseErrorKind::UsedBeforeDeclaration { name }, span);
+        }
+        self.scopes[innermost].push(name);
+    }
+
+    /// The slot of the latest declaration of `name` in scope.
+    fn lookup(&self, name: &str) -> Option<Slot> {
+        self.scopes
+            .iter()
+            .rev()
+            .enumerate()
+            .find_map(|(depth, scope)| {
+                let index = scope.iter().rposition(|declared| declared == name)?;
+                Some(Slot { depth, index })
+            })
+    }
+
+    fn use_variable(&mut self, name: &str, span: Span) -> Option<Slot> {
+        let slot = self.lookup(name);
+        if slot.is_none() {
+            let innermost = self.scopes.len() - 1;
+            self.pending.push((name.to_string(), span, innermost));
+        }
+        slot
+    }
+
+    fn resolve_statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::VariableDeclaration { name, value, .. } => {
+                // The value is resolved first, so `let x = x` can not see
+                // the `x` it declares
+                self.resolve_expression(value);
+                self.declare(name.clone());
+            }
+            Statement::Assignment {
+                name,
+                slot,
+                value,
+                span,
+            } => {
+                self.resolve_expression(value);
+                *slot = self.use_variable(name, *span);
+            }
+            Statement::FunctionDeclaration { params, body, .. } => {
+                self.resolve_function(params, body)
+            }
+            Statement::IfStatement {
+                condition,
+                body,
+                else_body,
+                ..
+            } => {
+                self.resolve_expression(condition);
+                self.resolve_scoped(vec![], body);
+                if let Some(else_body) = else_body {
+                    self.resolve_scoped(vec![], else_body);
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                self.resolve_expression(condition);
+                self.resolve_scoped(vec![], body);
+            }
+            Statement::For {
+                variable,
+                start,
+                end,
+                body,
+                ..
+            } => {
+                self.resolve_expression(start);
+                self.resolve_expression(end);
+                self.resolve_scoped(vec![variable.clone()], body);
+            }
+            Statement::Break { .. } | Statement::Continue { .. } => {}
+            Statement::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.resolve_expression(value);
+                }
+            }
+            Statement::ExprStatement { expr, .. } => self.resolve_expression(expr),
+        }
+    }
+
+    fn resolve_expression(&mut self, expr: &mut Expression) {
+        match expr {
+            Expression::Identifier { val, slot, span } => {
+                *slot = self.use_variable(val, *span);
+            }
+            Expression::StringLiteral { .. }
+            | Expression::IntLiteral { .. }
+            | Expression::BooleanLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::CharLiteral { .. } => {}
+            Expression::FunctionCall { name, params, .. } => {
+                self.resolve_expression(name);
+                for param in params {
+                    self.resolve_expression(param);
+                }
+            }
+            Expression::BinaryExpr { lhs, rhs, .. } => {
+                self.resolve_expression(lhs);
+                self.resolve_expression(rhs);
+            }
+            Expression::UnaryExpr { child, .. } => self.resolve_expression(child),
+            Expression::Cast { expr, .. } => self.resolve_expression(expr),
+            Expression::Function { params, body, .. } => self.resolve_function(params, body),
+        }
+    }
+}
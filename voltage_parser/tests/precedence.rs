@@ -0,0 +1,81 @@
+//! Checks the grouping the parser picks for each expression against its
+//! S-expression rendering.
+
+use voltage_ast::statements::Statement;
+use voltage_lexer::Lexer;
+use voltage_parser::Parser;
+
+fn parse(source: &str) -> String {
+    let tokens = Lexer::new(source).lex().unwrap();
+    let (ast, errors) = Parser::new(tokens).parse();
+    assert!(errors.is_empty(), "{source}: {errors:?}");
+    match &ast[..] {
+        [Statement::ExprStatement { expr, .. }] => expr.to_string(),
+        other => panic!("{source}: expected one expression, found {other:?}"),
+    }
+}
+
+#[test]
+fn precedence() {
+    let cases = [
+        // Arithmetic
+        ("1 + 2 * 3", "(+ 1 (* 2 3))"),
+        ("1 * 2 + 3", "(+ (* 1 2) 3)"),
+        ("a - b - c", "(- (- a b) c)"),
+        ("a / b * c", "(* (/ a b) c)"),
+        // Grouping
+        ("(a + b) * c", "(* (+ a b) c)"),
+        ("a * (b + c)", "(* a (+ b c))"),
+        ("((a))", "a"),
+        ("(a - (b - c))", "(- a (- b c))"),
+        // Prefix operators
+        ("-a * b", "(* (- a) b)"),
+        ("-(a * b)", "(- (* a b))"),
+        ("a - -b", "(- a (- b))"),
+        ("!a == b", "(== (not a) b)"),
+        ("not (a == b)", "(not (== a b))"),
+        ("-(2 + 3)", "(- (+ 2 3))"),
+        // Comparison and logic
+        ("a + 1 < b * 2", "(< (+ a 1) (* b 2))"),
+        ("a < b and c > d", "(and (< a b) (> c d))"),
+        ("a or b and c", "(or a (and b c))"),
+        ("a and b or c and d", "(or (and a b) (and c d))"),
+        ("(a or b) and c", "(and (or a b) c)"),
+        // Calls
+        ("f()", "(call f)"),
+        ("f(1, 2 + 3)", "(call f 1 (+ 2 3))"),
+        ("get_fn()(1)", "(call (call get_fn) 1)"),
+        ("f(a)(b)(c)", "(call (call (call f a) b) c)"),
+        ("(f)(x)", "(call f x)"),
+        ("(a + b)(c)", "(call (+ a b) c)"),
+        ("-f(x)", "(- (call f x))"),
+        ("a + f(b) * c", "(+ a (* (call f b) c))"),
+        ("f(g(x), (y))", "(call f (call g x) y)"),
+        // Casts bind between prefix and infix operators
+        ("a * b as i8", "(* a (as b i8))"),
+        ("-x as float + 1.0", "(+ (as (- x) float) 1.0)"),
+        ("f(x) as i64 as int", "(as (as (call f x) i64) int)"),
+        // Wrapping operators bind like the ones they wrap
+        ("a +% b * 2", "(+% a (* b 2))"),
+        ("a *% b -% c", "(-% (*% a b) c)"),
+        ("wrapping_add(a, b)", "(call wrapping_add a b)"),
+    ];
+
+    for (source, expected) in cases {
+        assert_eq!(parse(source), expected, "{source}");
+    }
+}
+
+#[test]
+fn unclosed_group_is_an_error() {
+    let tokens = Lexer::new("let x: int = (1 + 2\nlet y: int = 3")
+        .lex()
+        .unwrap();
+    let (ast, errors) = Parser::new(tokens).parse();
+    assert_eq!(ast.len(), 1);
+    let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        errors,
+        ["[PARSER] Error @ 2:1-2:4: expected RParen, found Let"]
+    );
+}
@@ -0,0 +1,152 @@
+use std::fmt;
+
+use voltage_ast::{Operator, Type, TYPE_NAMES};
+use voltage_lexer::span::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeErrorKind {
+    /// A `let` without a type annotation whose value has no known type.
+    CannotInfer {
+        name: String,
+    },
+    /// A `let` whose value is the result of a function returning nothing.
+    VoidValue {
+        name: String,
+    },
+    /// A value of type `found` where one of type `expected` was required.
+    Mismatch {
+        expected: Type,
+        found: Type,
+    },
+    UndeclaredVariable {
+        name: String,
+    },
+    UndeclaredFunction {
+        name: String,
+    },
+    AssignToImmutable {
+        name: String,
+    },
+    ArgumentCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    ReturnOutsideFunction,
+    /// A binary operator applied to operands it is not defined for.
+    InvalidOperands {
+        op: Operator,
+        lhs: Type,
+        rhs: Type,
+    },
+    /// A prefix operator applied to an operand it is not defined for.
+    InvalidOperand {
+        op: Operator,
+        operand: Type,
+    },
+    /// An unsuffixed integer literal used as a type too narrow to hold it.
+    LiteralOutOfRange {
+        val: i64,
+        r#type: Type,
+    },
+    /// A `for` range whose bounds are not integers of the same type.
+    InvalidRange {
+        start: Type,
+        end: Type,
+    },
+    InvalidCast {
+        from: Type,
+        to: Type,
+    },
+    /// A call to a value that is not a function.
+    NotCallable {
+        found: Type,
+    },
+    /// A function returning `r#type` whose body can end without a
+    /// `return`.
+    MissingReturn {
+        function: String,
+        r#type: Type,
+    },
+    /// A type annotation naming no type, `of` being what it annotates.
+    UnknownType {
+        name: String,
+        of: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeError {
+    pub kind: TypeErrorKind,
+    pub span: Span,
+}
+
+impl fmt::Display for TypeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeErrorKind::CannotInfer { name } => write!(
+                f,
+                "can not infer the type of '{name}', add a type annotation"
+            ),
+            TypeErrorKind::VoidValue { name } => {
+                write!(f, "'{name}' can not hold a value of type void")
+            }
+            TypeErrorKind::Mismatch { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            TypeErrorKind::UndeclaredVariable { name } => {
+                write!(f, "no variable '{name}' in scope")
+            }
+            TypeErrorKind::UndeclaredFunction { name } => {
+                write!(f, "no function '{name}' in scope")
+            }
+            TypeErrorKind::AssignToImmutable { name } => {
+                write!(f, "can not assign twice to immutable variable '{name}'")
+            }
+            TypeErrorKind::ArgumentCount {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "'{name}' takes {expected} argument(s) but {found} were given"
+            ),
+            TypeErrorKind::ReturnOutsideFunction => write!(f, "return outside of a function"),
+            TypeErrorKind::InvalidOperands { op, lhs, rhs } => {
+                write!(f, "can not apply '{op}' to {lhs} and {rhs}")
+            }
+            TypeErrorKind::InvalidOperand { op, operand } => {
+                write!(f, "can not apply '{op}' to {operand}")
+            }
+            TypeErrorKind::LiteralOutOfRange { val, r#type } => {
+                write!(f, "integer literal '{val}' is out of range for {type}")
+            }
+            TypeErrorKind::InvalidRange { start, end } => write!(
+                f,
+                "a range must be between integers of the same type, found {start}..{end}"
+            ),
+            TypeErrorKind::InvalidCast { from, to } => {
+                write!(f, "can not cast {from} to {to}")
+            }
+            TypeErrorKind::NotCallable { found } => {
+                write!(f, "can not call a value of type {found}")
+            }
+            TypeErrorKind::MissingReturn { function, r#type } => {
+                write!(f, "{function} can reach its end without returning {type}")
+            }
+            TypeErrorKind::UnknownType { name, of } => write!(
+                f,
+                "unknown type name '{name}' for {of}, expected one of {}",
+                TYPE_NAMES.join(", ")
+            ),
+        }
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[TYPECHECK] Error @ {}: {}", self.span, self.kind)
+    }
+}
+
+impl std::error::Error for TypeError {}
@@ -1,14 +1,932 @@
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
+use std::collections::HashMap;
+
+use voltage_ast::{
+    expressions::Expression, statements::Statement, FuncParam, Mutability, Operator, Type,
+};
+use voltage_lexer::span::Span;
+
+use error::{TypeError, TypeErrorKind};
+
+pub mod error;
+
+/// Checks names and types across the program before it is run, and infers
+/// the type of every `let` declared without one, writing it back into the
+/// AST.
+pub fn check(ast: &mut [Statement]) -> Vec<TypeError> {
+    let mut checker = TypeChecker::default();
+    checker.check_block(ast, vec![]);
+    checker.errors
+}
+
+/// A variable in scope.
+#[derive(Debug, Clone)]
+struct Binding {
+    r#type: Type,
+    mutability: Mutability,
+}
+
+#[derive(Default)]
+pub struct TypeChecker {
+    /// Variables in scope, innermost scope last.
+    scopes: Vec<HashMap<String, Binding>>,
+    /// Return types of the functions being checked, innermost last.
+    return_types: Vec<Type>,
+    errors: Vec<TypeError>,
+}
+
+impl TypeChecker {
+    /// Checks `statements` in a new scope, seeded with the immutable
+    /// `bindings`.
+    fn check_block(&mut self, statements: &mut [Statement], bindings: Vec<(String, Type)>) {
+        let scope = bindings
+            .into_iter()
+            .map(|(name, r#type)| {
+                let mutability = Mutability::Immutable;
+                (name, Binding { r#type, mutability })
+            })
+            .collect();
+        self.scopes.push(scope);
+        // Functions are in scope for the whole block, so that they can call
+        // each other whatever order they are written in
+        for statement in statements.iter() {
+            if let Statement::FunctionDeclaration {
+                name,
+                params,
+                return_type,
+                ..
+            } = statement
+            {
+                let r#type = function_type(params, return_type);
+                self.declare(name.clone(), r#type, Mutability::Immutable);
+            }
+        }
+        for statement in statements {
+            self.check_statement(statement);
+        }
+        self.scopes.pop();
+    }
+
+    fn check_statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::VariableDeclaration {
+                name,
+                value,
+                r#type,
+                mutability,
+                ..
+            } => {
+                if let Some(r#type) = r#type {
+                    self.check_type_name(r#type, &|| format!("'{name}'"));
+                }
+                match r#type {
+                    Some(expected) => self.check_value(value, expected),
+                    None => {
+                        let errors = self.errors.len();
+                        match self.check_expression(value) {
+                            // Only reported if the value is not already wrong
+                            Type::Unknown if errors == self.errors.len() => self.error(
+                                TypeErrorKind::CannotInfer { name: name.clone() },
+                                value.span(),
+                            ),
+                            Type::Unknown => {}
+                            Type::Void => self.error(
+                                TypeErrorKind::VoidValue { name: name.clone() },
+                                value.span(),
+                            ),
+                            found => *r#type = Some(found),
+                        }
+                    }
+                }
+                let r#type = r#type.clone().unwrap_or(Type::Unknown);
+                self.declare(name.clone(), r#type, *mutability);
+            }
+            Statement::Assignment {
+                name, value, span, ..
+            } => match self.lookup(name).cloned() {
+                None => {
+                    self.check_expression(value);
+                    let kind = TypeErrorKind::UndeclaredVariable { name: name.clone() };
+                    self.error(kind, *span);
+                }
+                Some(Binding {
+                    mutability: Mutability::Immutable | Mutability::Const,
+                    ..
+                }) => {
+                    self.check_expression(value);
+                    let kind = TypeErrorKind::AssignToImmutable { name: name.clone() };
+                    self.error(kind, *span);
+                }
+                Some(binding) => self.check_value(value, &binding.r#type),
+            },
+            Statement::FunctionDeclaration {
+                name,
+                params,
+                body,
+                return_type,
+                span,
+            } => self.check_function(params, return_type, body, &format!("'{name}'"), *span),
+            Statement::IfStatement {
+                condition,
+                body,
+                else_body,
+                ..
+            } => {
+                self.check_value(condition, &Type::Bool);
+                self.check_block(body, vec![]);
+                if let Some(else_body) = else_body {
+                    self.check_block(else_body, vec![]);
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                self.check_value(condition, &Type::Bool);
+                self.check_block(body, vec![]);
+            }
+            Statement::For {
+                variable,
+                start,
+                end,
+                body,
+                ..
+            } => {
+                let start_type = self.check_expression(start);
+                let end_type = self.check_expression(end);
+                let (r#type, end_type) = self.unify(start, start_type, end, end_type);
+                if !(r#type.is_int() || r#type == Type::Unknown) || !accepts(&r#type, &end_type) {
+                    let kind = TypeErrorKind::InvalidRange {
+                        start: r#type.clone(),
+                        end: end_type,
+                    };
+                    self.error(kind, start.span().to(end.span()));
+                }
+                self.check_block(body, vec![(variable.clone(), r#type)]);
+            }
+            Statement::Return { value, span } => match (self.return_types.last().cloned(), value) {
+                (Some(expected), Some(value)) => self.check_value(value, &expected),
+                (Some(expected), None) => {
+                    if !accepts(&expected, &Type::Void) {
+                        let found = Type::Void;
+                        self.error(TypeErrorKind::Mismatch { expected, found }, *span);
+                    }
+                }
+                (None, value) => {
+                    if let Some(value) = value {
+                        self.check_expression(value);
+                    }
+                    self.error(TypeErrorKind::ReturnOutsideFunction, *span);
+                }
+            },
+            Statement::ExprStatement { expr, .. } => {
+                self.check_expression(expr);
+            }
+            Statement::Break { .. } | Statement::Continue { .. } => {}
+        }
+    }
+
+    fn declare(&mut self, name: String, r#type: Type, mutability: Mutability) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, Binding { r#type, mutability });
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn error(&mut self, kind: TypeErrorKind, span: Span) {
+        self.errors.push(TypeError { kind, span });
+    }
+
+    /// Checks the body of a function against its parameters and return
+    /// type. `name` describes the function in errors.
+    fn check_function(
+        &mut self,
+        params: &mut [FuncParam],
+        return_type: &mut Type,
+        body: &mut [Statement],
+        name: &str,
+        span: Span,
+    ) {
+        for param in params.iter_mut() {
+            let of = || format!("parameter '{}' of {name}", param.name);
+            self.check_type_name(&mut param.r#type, &of);
+        }
+        self.check_type_name(return_type, &|| format!("the return of {name}"));
+
+        let params = params
+            .iter()
+            .map(|FuncParam { name, r#type }| (name.clone(), r#type.clone()))
+            .collect();
+        self.return_types.push(return_type.clone());
+        self.check_block(body, params);
+        self.return_types.pop();
+
+        let returns_value = !matches!(return_type, Type::Void | Type::Unknown);
+        if returns_value && !always_returns(body) {
+            let kind = TypeErrorKind::MissingReturn {
+                function: name.to_string(),
+                r#type: return_type.clone(),
+            };
+            self.error(kind, span);
+        }
+    }
+
+    /// Records an error for each name in `r#type` that is not a type,
+    /// leaving it `Type::Unknown` from then on. `of` describes what the type
+    /// annotates.
+    fn check_type_name(&mut self, r#type: &mut Type, of: &dyn Fn() -> String) {
+        match r#type {
+            Type::Undefined(undefined) => {
+                let kind = TypeErrorKind::UnknownType {
+                    name: undefined.name.clone(),
+                    of: of(),
+                };
+                self.error(kind, undefined.span);
+                *r#type = Type::Unknown;
+            }
+            Type::Function {
+                params,
+                return_type,
+            } => {
+                for param in params {
+                    self.check_type_name(param, of);
+                }
+                self.check_type_name(return_type, of);
+            }
+            _ => {}
+        }
+    }
+
+    /// Checks `expr` where a value of type `expected` is required.
+    fn check_value(&mut self, expr: &mut Expression, expected: &Type) {
+        let found = self.check_expression(expr);
+        let found = self.adopt(expr, found, expected);
+        if !accepts(expected, &found) {
+            let kind = TypeErrorKind::Mismatch {
+                expected: expected.clone(),
+                found,
+            };
+            self.error(kind, expr.span());
+        }
+    }
+
+    /// The type of `expr` once it is used where `expected` is required.
+    /// Unsuffixed integer literals take the integer type they are used as,
+    /// so that e.g. `let x: i8 = 1` and `x + 1` are allowed; any other
+    /// value keeps the type `found`.
+    fn adopt(&mut self, expr: &mut Expression, found: Type, expected: &Type) -> Type {
+        if found != Type::Int || !expected.is_int() || !is_untyped_literal(expr) {
+            return found;
+        }
+        self.retype_literals(expr, expected);
+        expected.clone()
+    }
+
+    fn retype_literals(&mut self, expr: &mut Expression, r#type: &Type) {
+        match expr {
+            Expression::IntLiteral {
+                val,
+                r#type: literal_type,
+                span,
+            } => {
+                let fits = r#type.int_range().unwrap().contains(&i128::from(*val));
+                if !fits {
+                    let kind = TypeErrorKind::LiteralOutOfRange {
+                        val: *val,
+                        r#type: r#type.clone(),
+                    };
+                    self.error(kind, *span);
+                }
+                *literal_type = r#type.clone();
+            }
+            Expression::BinaryExpr { lhs, rhs, .. } => {
+                self.retype_literals(lhs, r#type);
+                self.retype_literals(rhs, r#type);
+            }
+            Expression::UnaryExpr { child, .. } => self.retype_literals(child, r#type),
+            _ => {}
+        }
+    }
+
+    /// The types of two operands used together, after either side that is
+    /// an unsuffixed integer literal takes the type of the other.
+    fn unify(
+        &mut self,
+        lhs: &mut Expression,
+        lhs_type: Type,
+        rhs: &mut Expression,
+        rhs_type: Type,
+    ) -> (Type, Type) {
+        let rhs_type = self.adopt(rhs, rhs_type, &lhs_type);
+        let lhs_type = self.adopt(lhs, lhs_type, &rhs_type);
+        (lhs_type, rhs_type)
+    }
+
+    /// The type `expr` evaluates to, recording any errors within it.
+    /// `Type::Unknown` if it can not be told, which is accepted anywhere so
+    /// that one mistake is only reported once.
+    pub fn check_expression(&mut self, expr: &mut Expression) -> Type {
+        match expr {
+            Expression::IntLiteral { r#type, .. } => r#type.clone(),
+            Expression::FloatLiteral { .. } => Type::Float,
+            Expression::BooleanLiteral { .. } => Type::Bool,
+            Expression::CharLiteral { .. } => Type::Char,
+            Expression::StringLiteral { .. } => Type::String,
+            Expression::Identifier { val, span, .. } => match self.lookup(val) {
+                Some(binding) => binding.r#type.clone(),
+                None => {
+                    let name = val.clone();
+                    self.error(TypeErrorKind::UndeclaredVariable { name }, *span);
+                    Type::Unknown
+                }
+            },
+            Expression::FunctionCall { name, params, span } => {
+                let callee = match name.as_mut() {
+                    Expression::Identifier { val, span, .. } if self.lookup(val).is_none() => {
+                        let kind = TypeErrorKind::UndeclaredFunction { name: val.clone() };
+                        self.error(kind, *span);
+                        Type::Unknown
+                    }
+                    name => self.check_expression(name),
+                };
+                let (expected, return_type) = match callee {
+                    Type::Function {
+                        params,
+                        return_type,
+                    } => (params, *return_type),
+                    Type::Unknown => {
+                        for param in params {
+                            self.check_expression(param);
+                        }
+                        return Type::Unknown;
+                    }
+                    found => {
+                        self.error(TypeErrorKind::NotCallable { found }, name.span());
+                        for param in params {
+                            self.check_expression(param);
+                        }
+                        return Type::Unknown;
+                    }
+                };
+
+                if expected.len() != params.len() {
+                    let kind = TypeErrorKind::ArgumentCount {
+                        name: name.to_string(),
+                        expected: expected.len(),
+                        found: params.len(),
+                    };
+                    self.error(kind, *span);
+                    for param in params {
+                        self.check_expression(param);
+                    }
+                } else {
+                    for (param, expected) in params.iter_mut().zip(&expected) {
+                        self.check_value(param, expected);
+                    }
+                }
+                return_type
+            }
+            Expression::BinaryExpr { op, lhs, rhs, span } => {
+                let lhs_type = self.check_expression(lhs);
+                let rhs_type = self.check_expression(rhs);
+                let (lhs, rhs) = self.unify(lhs, lhs_type, rhs, rhs_type);
+                let arithmetic = is_arithmetic(op);
+                if lhs == Type::Unknown || rhs == Type::Unknown {
+                    return if arithmetic {
+                        Type::Unknown
+                    } else {
+                        Type::Bool
+                    };
+                }
+
+                // Operands are never converted implicitly, so both sides
+                // must be of the same type
+                let valid = lhs == rhs
+                    && match op {
+                        Operator::Plus
+                        | Operator::Minus
+                        | Operator::Multiplication
+                        | Operator::Division => lhs.is_int() || lhs == Type::Float,
+                        Operator::WrappingPlus
+                        | Operator::WrappingMinus
+                        | Operator::WrappingMultiplication => lhs.is_int(),
+                        Operator::And | Operator::Or => lhs == Type::Bool,
+                        _ => true,
+                    };
+                if !valid {
+                    let op = op.clone();
+                    self.error(TypeErrorKind::InvalidOperands { op, lhs, rhs }, *span);
+                    return if arithmetic {
+                        Type::Unknown
+                    } else {
+                        Type::Bool
+                    };
+                }
+
+                if arithmetic {
+                    lhs
+                } else {
+                    Type::Bool
+                }
+            }
+            Expression::UnaryExpr { op, child, span } => {
+                let operand = self.check_expression(child);
+                let valid = match op {
+                    Operator::Not => operand == Type::Bool,
+                    _ => operand.is_int() || operand == Type::Float,
+                };
+                if operand == Type::Unknown || valid {
+                    return operand;
+                }
+                let op = op.clone();
+                self.error(TypeErrorKind::InvalidOperand { op, operand }, *span);
+                Type::Unknown
+            }
+            Expression::Function {
+                params,
+                return_type,
+                body,
+                span,
+            } => {
+                let name = "an anonymous function";
+                self.check_function(params, return_type, body, name, *span);
+                function_type(params, return_type)
+            }
+            Expression::Cast { expr, r#type, span } => {
+                let from = self.check_expression(expr);
+                let numeric = |r#type: &Type| r#type.is_int() || *r#type == Type::Float;
+                self.check_type_name(r#type, &|| "a cast".to_string());
+                if *r#type != Type::Unknown
+                    && from != Type::Unknown
+                    && !(numeric(&from) && numeric(r#type))
+                {
+                    let to = r#type.clone();
+                    self.error(TypeErrorKind::InvalidCast { from, to }, *span);
+                }
+                r#type.clone()
+            }
+        }
+    }
+}
+
+/// Whether `expr` is built only from unsuffixed integer literals, and so
+/// may be given any integer type.
+fn is_untyped_literal(expr: &Expression) -> bool {
+    match expr {
+        Expression::IntLiteral { r#type, .. } => *r#type == Type::Int,
+        Expression::BinaryExpr { op, lhs, rhs, .. } => {
+            is_arithmetic(op) && is_untyped_literal(lhs) && is_untyped_literal(rhs)
+        }
+        Expression::UnaryExpr {
+            op: Operator::Minus | Operator::Plus,
+            child,
+            ..
+        } => is_untyped_literal(child),
+        _ => false,
+    }
+}
+
+/// Whether running `body` always ends in a `return`, or never ends.
+fn always_returns(body: &[Statement]) -> bool {
+    body.iter().any(|statement| match statement {
+        Statement::Return { .. } => true,
+        Statement::IfStatement {
+            body,
+            else_body: Some(else_body),
+            ..
+        } => always_returns(body) && always_returns(else_body),
+        Statement::While {
+            condition: Expression::BooleanLiteral { val: true, .. },
+            body,
+            ..
+        } => !breaks(body),
+        _ => false,
+    })
+}
+
+/// Whether `body` has a `break` out of the loop it is the body of.
+fn breaks(body: &[Statement]) -> bool {
+    body.iter().any(|statement| match statement {
+        Statement::Break { .. } => true,
+        Statement::IfStatement {
+            body, else_body, ..
+        } => breaks(body) || else_body.as_deref().is_some_and(breaks),
+        _ => false,
+    })
+}
+
+/// The type of a function, any name in it that is not a type being
+/// `Type::Unknown`.
+fn function_type(params: &[FuncParam], return_type: &Type) -> Type {
+    Type::Function {
+        params: params.iter().map(|param| known(&param.r#type)).collect(),
+        return_type: Box::new(known(return_type)),
+    }
+}
+
+/// `r#type` with any name in it that is not a type made `Type::Unknown`.
+fn known(r#type: &Type) -> Type {
+    match r#type {
+        Type::Undefined(_) => Type::Unknown,
+        Type::Function {
+            params,
+            return_type,
+        } => Type::Function {
+            params: params.iter().map(known).collect(),
+            return_type: Box::new(known(return_type)),
+        },
+        r#type => r#type.clone(),
+    }
+}
+
+fn is_arithmetic(op: &Operator) -> bool {
+    matches!(
+        op,
+        Operator::Plus
+            | Operator::Minus
+            | Operator::Multiplication
+            | Operator::Division
+            | Operator::WrappingPlus
+            | Operator::WrappingMinus
+            | Operator::WrappingMultiplication
+    )
+}
+
+/// Whether a value of type `found` can be used where `expected` is
+/// required.
+fn accepts(expected: &Type, found: &Type) -> bool {
+    match (expected, found) {
+        (Type::Unknown, _) | (_, Type::Unknown) => true,
+        (
+            Type::Function {
+                params: expected_params,
+                return_type: expected_return,
+            },
+            Type::Function {
+                params: found_params,
+                return_type: found_return,
+            },
+        ) => {
+            expected_params.len() == found_params.len()
+                && expected_params
+                    .iter()
+                    .zip(found_params)
+                    .all(|(expected, found)| accepts(expected, found))
+                && accepts(expected_return, found_return)
+        }
+        _ => expected == found,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use voltage_lexer::Lexer;
+    use voltage_parser::Parser;
+
+    fn check_source(source: &str) -> (Vec<Statement>, Vec<String>) {
+        let tokens = Lexer::new(source).lex().unwrap();
+        let (mut ast, errors) = Parser::new(tokens).parse();
+        assert!(errors.is_empty(), "{errors:?}");
+        let errors = check(&mut ast);
+        (ast, errors.iter().map(ToString::to_string).collect())
+    }
+
+    fn declared_types(statements: &[Statement]) -> Vec<(&str, Option<Type>)> {
+        statements
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::VariableDeclaration { name, r#type, .. } => {
+                    Some((name.as_str(), r#type.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn let_types_are_inferred_from_the_value() {
+        let (ast, errors) = check_source(
+            "func half(x: float): float
+                 let y = x / 2.0
+                 return y
+             end
+             let a = 10
+             let b = 10i8 * 2i8
+             let c: i64 = 1
+             let d = c + 2
+             let e = half(1.0)
+             let f = -e
+             let g = a < 2 and not true
+             let h = 'h'
+             let i = half",
+        );
+
+        assert_eq!(
+            declared_types(&ast),
+            [
+                ("a", Some(Type::Int)),
+                ("b", Some(Type::Int8)),
+                ("c", Some(Type::Int64)),
+                ("d", Some(Type::Int64)),
+                ("e", Some(Type::Float)),
+                ("f", Some(Type::Float)),
+                ("g", Some(Type::Bool)),
+                ("h", Some(Type::Char)),
+                (
+                    "i",
+                    Some(Type::Function {
+                        params: vec![Type::Float],
+                        return_type: Box::new(Type::Float),
+                    })
+                ),
+            ]
+        );
+        let Statement::FunctionDeclaration { body, .. } = &ast[0] else {
+            panic!("expected a function, found {:?}", ast[0]);
+        };
+        assert_eq!(declared_types(body), [("y", Some(Type::Float))]);
+
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn inferred_types_follow_scopes() {
+        let (ast, errors) = check_source(
+            "let x = 1.5
+             for x in 0..3 {
+                 let inner = x
+             }
+             let outer = x",
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let Statement::For { body, .. } = &ast[1] else {
+            panic!("expected a for loop, found {:?}", ast[1]);
+        };
+        assert_eq!(declared_types(body), [("inner", Some(Type::Int))]);
+        assert_eq!(declared_types(&ast)[1], ("outer", Some(Type::Float)));
+    }
+
+    #[test]
+    fn mismatches_are_reported_where_they_occur() {
+        let (_, errors) = check_source(
+            "let x: int = \"hi\"
+             func add(a: int, b: int): int
+                 return a == b
+             end
+             let mut sum: float = add(1, 2.5)
+             let n: int = add(1)
+             let y = x + true
+             if x { }
+             const limit: int = -'c'
+             limit = 2
+             missing = undeclared(z)
+             return 0",
+        );
+        assert_eq!(
+            errors,
+            [
+                "[TYPECHECK] Error @ 1:14-1:18: expected int, found string",
+                "[TYPECHECK] Error @ 3:25-3:31: expected int, found bool",
+                "[TYPECHECK] Error @ 5:42-5:45: expected int, found float",
+                "[TYPECHECK] Error @ 5:35-5:46: expected float, found int",
+                "[TYPECHECK] Error @ 6:27-6:33: 'add' takes 2 argument(s) but 1 were given",
+                "[TYPECHECK] Error @ 7:22-7:30: can not apply '+' to int and bool",
+                "[TYPECHECK] Error @ 8:17-8:18: expected bool, found int",
+                "[TYPECHECK] Error @ 9:33-9:37: can not apply '-' to char",
+                "[TYPECHECK] Error @ 10:14-10:23: can not assign twice to immutable variable 'limit'",
+                "[TYPECHECK] Error @ 11:24-11:34: no function 'undeclared' in scope",
+                "[TYPECHECK] Error @ 11:35-11:36: no variable 'z' in scope",
+                "[TYPECHECK] Error @ 11:14-11:37: no variable 'missing' in scope",
+                "[TYPECHECK] Error @ 12:14-12:22: return outside of a function",
+            ]
+        );
+    }
+
+    #[test]
+    fn well_typed_programs_have_no_errors() {
+        let (_, errors) = check_source(
+            "func fib(n: int): int
+                 if n < 2 {
+                     return n
+                 }
+                 return fib(n - 1) + fib(n - 2)
+             end
+             let mut total: i64 = 0
+             for i in 0..10 {
+                 total += fib(i) as i64
+             }
+             let name: string = \"total\"
+             let big: bool = total > 100 and not (name == \"\")",
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn integer_widths_do_not_mix_implicitly() {
+        let (ast, errors) = check_source(
+            "let a: i8 = 100
+             let b: i16 = 1000
+             let c = a + 1
+             let d = a + b
+             let e: i8 = 200
+             let f: i32 = (b as i32 +% 1) * 2
+             let g: float = b
+             let h = 2.5 as i64 + a as i64
+             let i = true as int
+             for n in 0i16..b { }
+             for n in 0..2.5 { }",
+        );
+        assert_eq!(
+            errors,
+            [
+                "[TYPECHECK] Error @ 4:22-4:27: can not apply '+' to i8 and i16",
+                "[TYPECHECK] Error @ 5:26-5:29: integer literal '200' is out of range for i8",
+                "[TYPECHECK] Error @ 7:29-7:30: expected float, found i16",
+                "[TYPECHECK] Error @ 9:22-9:33: can not cast bool to int",
+                "[TYPECHECK] Error @ 11:23-11:29: a range must be between integers of the same type, found int..float",
+            ]
+        );
+
+        assert_eq!(
+            declared_types(&ast)[2..4],
+            [("c", Some(Type::Int8)), ("d", None)]
+        );
+        assert_eq!(declared_types(&ast)[7], ("h", Some(Type::Int64)));
+
+        // Literals take the type they are used as
+        let Statement::VariableDeclaration { value, .. } = &ast[5] else {
+            panic!("expected a declaration, found {:?}", ast[5]);
+        };
+        let Expression::BinaryExpr { lhs, rhs, .. } = value else {
+            panic!("expected a binary expression, found {value:?}");
+        };
+        assert!(matches!(
+            rhs.as_ref(),
+            Expression::IntLiteral {
+                r#type: Type::Int32,
+                ..
+            }
+        ));
+        assert!(matches!(
+            lhs.as_ref(),
+            Expression::BinaryExpr {
+                op: Operator::WrappingPlus,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn type_names_must_name_a_type() {
+        let (ast, errors) = check_source(
+            "let s: string = \"x\"
+             let b: bool = s == \"y\"
+             let n: integer = 1
+             func f(a: strng): nothing
+             end
+             let c = 1 as long",
+        );
+        assert_eq!(
+            declared_types(&ast)[..2],
+            [("s", Some(Type::String)), ("b", Some(Type::Bool))]
+        );
+
+        let names = "char, i8, i16, i32, i64, int, float, bool, string, nil, null, void";
+        assert_eq!(
+            errors,
+            [
+                format!("[TYPECHECK] Error @ 3:21-3:28: unknown type name 'integer' for 'n', expected one of {names}"),
+                format!("[TYPECHECK] Error @ 4:24-4:29: unknown type name 'strng' for parameter 'a' of 'f', expected one of {names}"),
+                format!("[TYPECHECK] Error @ 4:32-4:39: unknown type name 'nothing' for the return of 'f', expected one of {names}"),
+                format!("[TYPECHECK] Error @ 6:27-6:31: unknown type name 'long' for a cast, expected one of {names}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn functions_are_values_with_function_types() {
+        let (ast, errors) = check_source(
+            "func apply(f: func(int): int, x: int): int
+                 return f(x)
+             end
+             func adder(n: int): func(int): int
+                 return func(x: int): int
+                     return x + n
+                 end
+             end
+             let add_two = adder(2)
+             let four = apply(add_two, 2)
+             let six = apply(func(x: int): int
+                 return x * 3
+             end, 2)
+             apply(adder, 1)
+             apply(func(x: float): float
+                 return x
+             end, 1)
+             four(1)
+             let g: func(strin): int = add_two",
+        );
+
+        let types: Vec<String> = declared_types(&ast)
+            .into_iter()
+            .map(|(name, r#type)| format!("{name}: {}", r#type.unwrap()))
+            .collect();
+        assert_eq!(
+            types,
+            [
+                "add_two: func(int): int",
+                "four: int",
+                "six: int",
+                "g: func(unknown): int",
+            ]
+        );
+        assert_eq!(
+            errors,
+            [
+                "[TYPECHECK] Error @ 14:20-14:25: expected func(int): int, found func(int): func(int): int",
+                "[TYPECHECK] Error @ 15:20-17:17: expected func(int): int, found func(float): float",
+                "[TYPECHECK] Error @ 18:14-18:18: can not call a value of type int",
+                "[TYPECHECK] Error @ 19:26-19:31: unknown type name 'strin' for 'g', expected one of char, i8, i16, i32, i64, int, float, bool, string, nil, null, void",
+            ]
+        );
+    }
+
+    #[test]
+    fn functions_must_return_on_every_path() {
+        let (_, errors) = check_source(
+            "func none(): int
+                 let x = 1
+             end
+             func one_branch(n: int): int
+                 if n > 0 {
+                     return 1
+                 }
+             end
+             func both(n: int): int
+                 if n > 0 {
+                     return 1
+                 } else {
+                     return 2
+                 }
+             end
+             func forever(): int
+                 while true {
+                     return 1
+                 }
+             end
+             func until_break(): int
+                 while true {
+                     if true { break }
+                 }
+             end
+             let f = func(): bool
+             end
+             func nothing()
+             end",
+        );
+        assert_eq!(
+            errors,
+            [
+                "[TYPECHECK] Error @ 1:1-3:17: 'none' can reach its end without returning int",
+                "[TYPECHECK] Error @ 4:14-8:17: 'one_branch' can reach its end without returning int",
+                "[TYPECHECK] Error @ 21:14-25:17: 'until_break' can reach its end without returning int",
+                "[TYPECHECK] Error @ 26:22-27:17: an anonymous function can reach its end without returning bool",
+            ]
+        );
+    }
+
+    #[test]
+    fn bare_returns_only_leave_void_functions() {
+        let (_, errors) = check_source(
+            "func early(n: int)
+                 if n > 0 { return }
+                 let x = n
+             end
+             func count(): int
+                 return
+             end",
+        );
+        assert_eq!(
+            errors,
+            ["[TYPECHECK] Error @ 6:18-6:24: expected int, found void"]
+        );
+    }
 
     #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+    fn variables_can_not_hold_void() {
+        let (ast, errors) = check_source(
+            "func nothing()
+                 return
+             end
+             let x = nothing()",
+        );
+        assert_eq!(declared_types(&ast), [("x", None)]);
+        assert_eq!(
+            errors,
+            ["[TYPECHECK] Error @ 4:22-4:31: 'x' can not hold a value of type void"]
+        );
     }
 }